@@ -1,7 +1,7 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
 use fskc::{
-    FractalNode, RovingSelector, LayeredCrypto,
-    EntropyBuilder, RngEntropy, PhysicalEntropy, EntropySource,
+    FractalNode, RovingSelector, LayeredCrypto, LayerConfig,
+    EntropyBuilder, Key256,
 };
 use rand::{SeedableRng, RngCore};
 use rand_chacha::ChaCha20Rng;
@@ -10,11 +10,14 @@ fn bench_entropy(c: &mut Criterion) {
     let mut group = c.benchmark_group("Entropy");
     
     // Test different entropy source combinations
-    let data = vec![0u8; 1024];
     let rng1 = ChaCha20Rng::seed_from_u64(12345);
     let rng2 = ChaCha20Rng::seed_from_u64(67890);
-    let physical_data = vec![0x42; 1024];
-    let stellar_data = vec![0x17; 1024];
+    // Constant recordings would be quarantined by the health tests, and
+    // each recording must outlast the reseeds of a benchmark run
+    let mut physical_data = vec![0u8; 64 * 1024];
+    let mut stellar_data = vec![0u8; 64 * 1024];
+    ChaCha20Rng::seed_from_u64(1).fill_bytes(&mut physical_data);
+    ChaCha20Rng::seed_from_u64(2).fill_bytes(&mut stellar_data);
 
     // Single RNG source
     group.bench_function("single_rng", |b| {
@@ -73,6 +76,7 @@ fn bench_entropy(c: &mut Criterion) {
 
 fn bench_layered_crypto(c: &mut Criterion) {
     let mut group = c.benchmark_group("LayeredCrypto");
+    let seed = Key256::insecure_from_u64(12345);
     
     // Test different data sizes
     for size in [64, 256, 1024, 4096].iter() {
@@ -81,11 +85,12 @@ fn bench_layered_crypto(c: &mut Criterion) {
             size,
             |b, &size| {
                 let data = vec![0u8; size];
-                let mut crypto = LayeredCrypto::new(67890);
+                let mut crypto = LayeredCrypto::new(&Key256::insecure_from_u64(67890));
                 b.iter(|| {
                     black_box(crypto.encrypt(
                         black_box(&data),
-                        black_box(12345),
+                        black_box(b""),
+                        black_box(&seed),
                     ))
                 });
             },
@@ -98,7 +103,7 @@ fn bench_layered_crypto(c: &mut Criterion) {
         b.iter(|| {
             black_box(FractalNode::generate(
                 black_box(data.clone()),
-                black_box(12345),
+                black_box(&seed),
                 black_box(3),
                 black_box(64),
             ))
@@ -106,11 +111,12 @@ fn bench_layered_crypto(c: &mut Criterion) {
     });
 
     group.bench_function("basic_vs_layered/layered", |b| {
-        let mut crypto = LayeredCrypto::new(67890);
+        let mut crypto = LayeredCrypto::new(&Key256::insecure_from_u64(67890));
         b.iter(|| {
             black_box(crypto.encrypt(
                 black_box(&data),
-                black_box(12345),
+                black_box(b""),
+                black_box(&seed),
             ))
         });
     });
@@ -118,15 +124,17 @@ fn bench_layered_crypto(c: &mut Criterion) {
     // Full cycle (encrypt + decrypt)
     group.bench_function("full_cycle", |b| {
         let data = vec![0u8; 1024];
-        let mut crypto = LayeredCrypto::new(67890);
+        let mut crypto = LayeredCrypto::new(&Key256::insecure_from_u64(67890));
         b.iter(|| {
             let encrypted = crypto.encrypt(
                 black_box(&data),
-                black_box(12345),
+                black_box(b""),
+                black_box(&seed),
             ).unwrap();
             black_box(crypto.decrypt(
                 black_box(&encrypted),
-                black_box(12345),
+                black_box(b""),
+                black_box(&seed),
             ))
         });
     });
@@ -136,6 +144,7 @@ fn bench_layered_crypto(c: &mut Criterion) {
 
 fn bench_encryption(c: &mut Criterion) {
     let mut group = c.benchmark_group("Encryption");
+    let seed = Key256::insecure_from_u64(12345);
     
     // Test different data sizes
     for size in [64, 256, 1024, 4096].iter() {
@@ -147,7 +156,7 @@ fn bench_encryption(c: &mut Criterion) {
                 b.iter(|| {
                     black_box(FractalNode::generate(
                         black_box(data.clone()),
-                        black_box(12345),
+                        black_box(&seed),
                         black_box(3),
                         black_box(16),
                     ))
@@ -166,7 +175,7 @@ fn bench_encryption(c: &mut Criterion) {
                 b.iter(|| {
                     black_box(FractalNode::generate(
                         black_box(data.clone()),
-                        black_box(12345),
+                        black_box(&seed),
                         black_box(depth),
                         black_box(16),
                     ))
@@ -178,8 +187,70 @@ fn bench_encryption(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_parallel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Parallel");
+    let seed = Key256::insecure_from_u64(12345);
+    group.sample_size(20);
+
+    // Fractal tree generation and decryption over 1 MiB
+    let data = vec![0u8; 1 << 20];
+    group.bench_function("fractal_generate/sequential", |b| {
+        b.iter(|| {
+            black_box(FractalNode::generate_sequential(
+                black_box(data.clone()),
+                black_box(&seed),
+                black_box(3),
+                black_box(4096),
+            ))
+        });
+    });
+
+    group.bench_function("fractal_generate/parallel", |b| {
+        b.iter(|| {
+            black_box(FractalNode::generate(
+                black_box(data.clone()),
+                black_box(&seed),
+                black_box(3),
+                black_box(4096),
+            ))
+        });
+    });
+
+    let node = FractalNode::generate(data.clone(), &seed, 3, 4096).unwrap();
+    group.bench_function("fractal_decrypt/sequential", |b| {
+        b.iter(|| black_box(node.decrypt_sequential()));
+    });
+
+    group.bench_function("fractal_decrypt/parallel", |b| {
+        b.iter(|| black_box(node.decrypt()));
+    });
+
+    // Symmetric layers over 4 MiB, whole message vs 64 KiB chunks
+    let data = vec![0u8; 4 << 20];
+    for (name, chunks) in [("symmetric/whole", 0), ("symmetric/chunked", 64 * 1024)] {
+        group.bench_function(name, |b| {
+            let config = LayerConfig::builder()
+                .add_aes()
+                .add_xchacha_poly()
+                .parallel_chunks(chunks)
+                .build();
+            let mut crypto = LayeredCrypto::with_config(&Key256::insecure_from_u64(67890), config);
+            b.iter(|| {
+                black_box(crypto.encrypt(
+                    black_box(&data),
+                    black_box(b""),
+                    black_box(&seed),
+                ))
+            });
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_roving_selector(c: &mut Criterion) {
     let mut group = c.benchmark_group("RovingSelector");
+    let seed = Key256::insecure_from_u64(12345);
     
     // Test different dimensions
     for dim in [4, 8, 16, 32].iter() {
@@ -188,7 +259,7 @@ fn bench_roving_selector(c: &mut Criterion) {
             dim,
             |b, &dim| {
                 let shared_data = vec![0u8; 100];
-                let mut selector = RovingSelector::new(dim, 5, 12345).unwrap();
+                let mut selector = RovingSelector::new(dim, 5, &seed).unwrap();
                 selector.map_data(&shared_data).unwrap();
                 
                 b.iter(|| {
@@ -205,9 +276,27 @@ fn bench_roving_selector(c: &mut Criterion) {
             particles,
             |b, &particles| {
                 let shared_data = vec![0u8; 100];
-                let mut selector = RovingSelector::new(8, particles, 12345).unwrap();
+                let mut selector = RovingSelector::new(8, particles, &seed).unwrap();
                 selector.map_data(&shared_data).unwrap();
-                
+
+                b.iter(|| {
+                    black_box(selector.step())
+                });
+            },
+        );
+    }
+
+    // Test different input sizes; a step scanning every point grows linearly
+    for size in [256, 4096, 65536].iter() {
+        group.bench_with_input(
+            BenchmarkId::new("input_size", size),
+            size,
+            |b, &size| {
+                let mut data = vec![0u8; size];
+                ChaCha20Rng::seed_from_u64(12345).fill_bytes(&mut data);
+                let mut selector = RovingSelector::new(8, 10, &seed).unwrap();
+                selector.map_data(&data).unwrap();
+
                 b.iter(|| {
                     black_box(selector.step())
                 });
@@ -220,6 +309,7 @@ fn bench_roving_selector(c: &mut Criterion) {
 
 fn bench_full_cycle(c: &mut Criterion) {
     let mut group = c.benchmark_group("FullCycle");
+    let seed = Key256::insecure_from_u64(12345);
     
     // Test complete encryption-decryption cycle
     for size in [64, 256, 1024].iter() {
@@ -231,20 +321,18 @@ fn bench_full_cycle(c: &mut Criterion) {
                 let shared_data = vec![0u8; 100];
                 
                 b.iter(|| {
-                    let mut selector = RovingSelector::new(8, 5, 12345).unwrap();
+                    let mut selector = RovingSelector::new(8, 5, &seed).unwrap();
                     selector.map_data(&shared_data).unwrap();
                     
-                    let mut combined_seed = 0u64;
+                    let mut selected = Vec::new();
                     for _ in 0..5 {
-                        let selected = selector.step().unwrap();
-                        for &byte in &selected {
-                            combined_seed = combined_seed.wrapping_mul(256).wrapping_add(byte as u64);
-                        }
+                        selected.extend(selector.step().unwrap());
                     }
+                    let combined_seed = Key256::from_material(&selected);
                     
                     let encrypted = FractalNode::generate(
                         data.clone(),
-                        combined_seed,
+                        &combined_seed,
                         3,
                         16,
                     ).unwrap();
@@ -264,6 +352,7 @@ criterion_group!(
     bench_roving_selector,
     bench_full_cycle,
     bench_layered_crypto,
+    bench_parallel,
     bench_entropy
 );
 criterion_main!(benches);
@@ -1,6 +1,6 @@
 use fskc::{
     LayeredCrypto, LayerConfig, RovingSelector,
-    EntropyBuilder, Result,
+    EntropyBuilder, Key256, Result,
 };
 use rand::{SeedableRng};
 use rand_chacha::ChaCha20Rng;
@@ -12,22 +12,22 @@ fn main() -> Result<()> {
 
     // First, use the roving selector to generate a seed from some shared data
     let shared_data = b"This could be any public data source that both parties have access to".to_vec();
-    let mut selector = RovingSelector::new(8, 5, 12345)?;
+    let mut selector = RovingSelector::new(8, 5, &Key256::insecure_from_u64(12345))?;
     
     // Map the shared data into high-dimensional space
     selector.map_data(&shared_data)?;
     
     // Run the selector for a few steps to generate entropy
-    let mut combined_seed = 0u64;
+    let mut selected = Vec::new();
     for _ in 0..5 {
-        let selected = selector.step()?;
-        // Combine the selected bytes into a seed
-        for &byte in &selected {
-            combined_seed = combined_seed.wrapping_mul(256).wrapping_add(byte as u64);
-        }
+        selected.extend(selector.step_bytes()?);
     }
 
-    println!("Generated initial seed from roving selector: {}", combined_seed);
+    // Condense the selected bytes into key material
+    let seed = Key256::from_material(&selected);
+    let master_key = seed.derive(b"master key");
+    let initial_seed = seed.derive(b"initial seed");
+    println!("Derived keys from {} bytes selected by the roving selector", selected.len());
 
     // Example 1: Basic layered encryption (FSKC + AES)
     println!("\nExample 1: Basic layered encryption");
@@ -36,10 +36,10 @@ fn main() -> Result<()> {
         .add_aes()
         .build();
 
-    let mut crypto = LayeredCrypto::with_config(combined_seed, config.clone());
-    let encrypted = crypto.encrypt(&message, combined_seed)?;
-    let mut crypto = LayeredCrypto::with_config(combined_seed, config);
-    let decrypted = crypto.decrypt(&encrypted, combined_seed)?;
+    let mut crypto = LayeredCrypto::with_config(&master_key, config.clone());
+    let encrypted = crypto.encrypt(&message, b"", &initial_seed)?;
+    let mut crypto = LayeredCrypto::with_config(&master_key, config);
+    let decrypted = crypto.decrypt(&encrypted, b"", &initial_seed)?;
     assert_eq!(message, decrypted);
     println!("Basic layered encryption successful!");
 
@@ -53,17 +53,17 @@ fn main() -> Result<()> {
         .build();
 
     // Create entropy from multiple RNGs
-    let rng1 = ChaCha20Rng::seed_from_u64(combined_seed);
-    let rng2 = ChaCha20Rng::seed_from_u64(combined_seed.wrapping_add(1));
+    let rng1 = ChaCha20Rng::from_entropy();
+    let rng2 = ChaCha20Rng::from_entropy();
     let entropy = EntropyBuilder::new()
         .add_rng(rng1, "ChaCha20 RNG 1")
         .add_rng(rng2, "ChaCha20 RNG 2")
         .build();
 
-    let mut crypto = LayeredCrypto::with_entropy(entropy, config.clone());
-    let encrypted = crypto.encrypt(&message, combined_seed)?;
-    let mut crypto = LayeredCrypto::with_config(combined_seed, config);
-    let decrypted = crypto.decrypt(&encrypted, combined_seed)?;
+    let mut crypto = LayeredCrypto::with_entropy(&master_key, entropy, config.clone());
+    let encrypted = crypto.encrypt(&message, b"", &initial_seed)?;
+    let mut crypto = LayeredCrypto::with_config(&master_key, config);
+    let decrypted = crypto.decrypt(&encrypted, b"", &initial_seed)?;
     assert_eq!(message, decrypted);
     println!("High-performance encryption successful!");
 
@@ -83,15 +83,15 @@ fn main() -> Result<()> {
     let stellar_data = vec![0x17; 1024]; // In practice, this would be real stellar data
     
     let entropy = EntropyBuilder::new()
-        .add_rng(ChaCha20Rng::seed_from_u64(combined_seed), "Base RNG")
+        .add_rng(ChaCha20Rng::from_entropy(), "Base RNG")
         .add_ligo_data(ligo_data)
         .add_stellar_parallax(stellar_data)
         .build();
 
-    let mut crypto = LayeredCrypto::with_entropy(entropy, config.clone());
-    let encrypted = crypto.encrypt(&message, combined_seed)?;
-    let mut crypto = LayeredCrypto::with_config(combined_seed, config);
-    let decrypted = crypto.decrypt(&encrypted, combined_seed)?;
+    let mut crypto = LayeredCrypto::with_entropy(&master_key, entropy, config.clone());
+    let encrypted = crypto.encrypt(&message, b"", &initial_seed)?;
+    let mut crypto = LayeredCrypto::with_config(&master_key, config);
+    let decrypted = crypto.decrypt(&encrypted, b"", &initial_seed)?;
     assert_eq!(message, decrypted);
     println!("Maximum security encryption successful!");
 
@@ -108,14 +108,14 @@ fn main() -> Result<()> {
     // Create entropy from multiple sources
     let custom_physical = vec![0x89; 1024]; // Custom physical measurements
     let entropy = EntropyBuilder::new()
-        .add_rng(ChaCha20Rng::seed_from_u64(combined_seed), "Base RNG")
+        .add_rng(ChaCha20Rng::from_entropy(), "Base RNG")
         .add_physical_source(custom_physical, "Custom Physical Source")
         .build();
 
-    let mut crypto = LayeredCrypto::with_entropy(entropy, config.clone());
-    let encrypted = crypto.encrypt(&message, combined_seed)?;
-    let mut crypto = LayeredCrypto::with_config(combined_seed, config);
-    let decrypted = crypto.decrypt(&encrypted, combined_seed)?;
+    let mut crypto = LayeredCrypto::with_entropy(&master_key, entropy, config.clone());
+    let encrypted = crypto.encrypt(&message, b"", &initial_seed)?;
+    let mut crypto = LayeredCrypto::with_config(&master_key, config);
+    let decrypted = crypto.decrypt(&encrypted, b"", &initial_seed)?;
     assert_eq!(message, decrypted);
     println!("Custom sequence encryption successful!");
 
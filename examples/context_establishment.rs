@@ -10,6 +10,7 @@ fn main() -> Result<()> {
     // Configure context establishment
     let context_config = ContextConfig {
         min_quality: 0.8,
+        min_entropy_per_bit: 0.5,
         required_windows: 3,
         max_age: Duration::from_secs(60),
     };
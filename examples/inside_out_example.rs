@@ -1,8 +1,8 @@
-use fskc::{SystemState, Result};
+use fskc::{Key256, SystemState, Result};
 
 fn main() -> Result<()> {
     // Create system state with seed
-    let mut state = SystemState::new(12345)?;
+    let mut state = SystemState::new(&Key256::insecure_from_u64(12345))?;
     
     // Test data
     let data = b"Hello, inside-out crypto!";
@@ -28,6 +28,7 @@ fn main() -> Result<()> {
                 sample_rate: 100.0,
                 signal_to_noise: 10.0,
                 temporal_consistency: 0.9,
+                min_entropy_per_bit: 0.8 + (i as f64 * 0.02),
             },
         };
 
@@ -43,6 +44,7 @@ fn main() -> Result<()> {
                 sample_rate: 100.0,
                 signal_to_noise: 12.0,
                 temporal_consistency: 0.95,
+                min_entropy_per_bit: 0.85 + (i as f64 * 0.01),
             },
         };
 
@@ -14,6 +14,7 @@ fn main() -> Result<()> {
     // Configure context establishment
     let context_config = ContextConfig {
         min_quality: 0.8,
+        min_entropy_per_bit: 0.5,
         required_windows: 3,
         max_age: Duration::from_secs(60),
     };
@@ -22,6 +23,7 @@ fn main() -> Result<()> {
     let keygen_config = KeyGenConfig {
         key_length: 32,       // 256-bit keys
         min_quality: 0.8,     // High quality requirement
+        min_entropy_per_bit: 0.5,  // Conservative min-entropy floor
         hash_iterations: 10000, // Strong KDF
     };
 
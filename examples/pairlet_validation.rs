@@ -43,6 +43,7 @@ fn main() -> Result<()> {
             sample_rate: 200.0,
             signal_to_noise: 14.01,
             temporal_consistency: 1.0,
+            min_entropy_per_bit: 0.79,
         },
     };
 
@@ -55,6 +56,7 @@ fn main() -> Result<()> {
             sample_rate: 200.0,
             signal_to_noise: 13.98,
             temporal_consistency: 1.0,
+            min_entropy_per_bit: 0.79,
         },
     };
 
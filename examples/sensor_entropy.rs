@@ -69,5 +69,18 @@ fn main() -> Result<()> {
     println!("Mean: {:.2}", mean);
     println!("Standard Deviation: {:.2}", variance.sqrt());
 
+    // Show how much entropy each source has been credited with
+    println!("\nEntropy Accounting:");
+    for stats in entropy.stats() {
+        println!(
+            "{}: {:.2} bits/byte ({}), {} bytes drawn, {:.1} bits credited",
+            stats.description,
+            stats.entropy_per_byte,
+            if stats.estimated { "estimated" } else { "declared" },
+            stats.bytes_drawn,
+            stats.credited_bits
+        );
+    }
+
     Ok(())
 }
@@ -0,0 +1,134 @@
+//! Entropy accounting for combined pools
+//!
+//! Each source is credited per byte it contributes, at the min-entropy
+//! rate it declares or, failing that, at the rate the SP 800-90B
+//! estimators assess over its startup samples. The pool keeps a ledger of
+//! bits credited since it last seeded its DRBG and only seeds once the
+//! ledger reaches the threshold of its [`GateConfig`].
+
+use super::estimators;
+use super::health::HealthStatus;
+use std::time::Duration;
+
+/// What a request does when the sources fall short of the threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GatePolicy {
+    /// Fail once the sampling rounds are used up
+    Refuse,
+    /// Keep sampling every `poll_interval` until `timeout` has passed
+    Block { poll_interval: Duration, timeout: Duration },
+}
+
+/// Credited entropy required before the DRBG (re)seeds
+#[derive(Debug, Clone)]
+pub struct GateConfig {
+    /// Fresh bits of min-entropy credited since the last seed
+    pub threshold_bits: f64,
+    /// Bytes drawn from every healthy source per sampling round
+    pub round_bytes: usize,
+    /// Sampling rounds one request runs before the policy applies
+    pub max_rounds: usize,
+    /// Behaviour once the rounds are used up
+    pub policy: GatePolicy,
+}
+
+impl Default for GateConfig {
+    fn default() -> Self {
+        Self {
+            threshold_bits: 256.0,  // DRBG security strength
+            round_bytes: 64,
+            max_rounds: 16,
+            policy: GatePolicy::Refuse,
+        }
+    }
+}
+
+/// Entropy accounting for one source of a combined pool
+#[derive(Debug, Clone)]
+pub struct SourceStats {
+    /// Description of the source
+    pub description: String,
+    /// Current health of the source
+    pub status: HealthStatus,
+    /// Min-entropy credited per byte, in bits
+    pub entropy_per_byte: f64,
+    /// True if the rate was estimated rather than declared by the source
+    pub estimated: bool,
+    /// Bytes drawn into the pool, excluding startup samples
+    pub bytes_drawn: u64,
+    /// Bits credited to the pool over the lifetime of the source
+    pub credited_bits: f64,
+}
+
+/// Running account of one source
+#[derive(Debug, Clone)]
+pub(crate) struct SourceAccount {
+    entropy_per_byte: f64,
+    estimated: bool,
+    bytes_drawn: u64,
+    credited_bits: f64,
+}
+
+impl SourceAccount {
+    /// Opens an account at the declared rate, or one estimated from `samples`
+    pub(crate) fn new(declared: Option<f64>, samples: &[u8]) -> Self {
+        let (rate, estimated) = match declared {
+            Some(rate) => (rate, false),
+            None => (estimators::estimate(samples).min_entropy_per_bit() * 8.0, true),
+        };
+        Self {
+            entropy_per_byte: if rate.is_nan() { 0.0 } else { rate.clamp(0.0, 8.0) },
+            estimated,
+            bytes_drawn: 0,
+            credited_bits: 0.0,
+        }
+    }
+
+    /// Records `len` bytes drawn from the source, returning the bits credited
+    pub(crate) fn credit(&mut self, len: usize) -> f64 {
+        let bits = len as f64 * self.entropy_per_byte;
+        self.bytes_drawn += len as u64;
+        self.credited_bits += bits;
+        bits
+    }
+
+    pub(crate) fn stats(&self, description: &str, status: HealthStatus) -> SourceStats {
+        SourceStats {
+            description: description.to_string(),
+            status,
+            entropy_per_byte: self.entropy_per_byte,
+            estimated: self.estimated,
+            bytes_drawn: self.bytes_drawn,
+            credited_bits: self.credited_bits,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_declared_and_estimated_rates() {
+        let declared = SourceAccount::new(Some(12.0), &[]);
+        assert_eq!(declared.entropy_per_byte, 8.0);
+        assert!(!declared.estimated);
+
+        let mut bytes = vec![0u8; 1024];
+        ChaCha20Rng::seed_from_u64(12345).fill_bytes(&mut bytes);
+        let mut random = SourceAccount::new(None, &bytes);
+        assert!(random.estimated);
+        assert!(random.entropy_per_byte > 4.0 && random.entropy_per_byte <= 8.0);
+
+        assert_eq!(random.credit(10), 10.0 * random.entropy_per_byte);
+        random.credit(6);
+        let stats = random.stats("ChaCha20", HealthStatus::Healthy);
+        assert_eq!(stats.bytes_drawn, 16);
+        assert!((stats.credited_bits - 16.0 * random.entropy_per_byte).abs() < 1e-9);
+
+        // Constant output earns no credit
+        assert_eq!(SourceAccount::new(None, &[0x42; 1024]).credit(64), 0.0);
+    }
+}
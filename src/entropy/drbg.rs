@@ -0,0 +1,186 @@
+//! Conditioning and deterministic random bit generation
+//!
+//! Raw source output is condensed with the SHA-256 `Hash_df` derivation
+//! function and seeds an HMAC-SHA256 DRBG, both as specified in NIST
+//! SP 800-90A. The DRBG only ever outputs bytes derived from its secret
+//! state, so no single source can bias or cancel the others.
+
+use crate::{FskcError, Result};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Security strength of the DRBG in bits
+pub const SECURITY_STRENGTH: usize = 256;
+
+/// Largest request served by a single generate call, in bytes
+pub const MAX_REQUEST_BYTES: usize = 1 << 16;
+
+/// Condenses `inputs` into `len` bytes with SHA-256 `Hash_df`
+pub fn hash_df(inputs: &[&[u8]], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len + 32);
+    let mut counter = 1u8;
+    while out.len() < len {
+        let mut hasher = Sha256::new();
+        hasher.update([counter]);
+        hasher.update(((len * 8) as u32).to_be_bytes());
+        for input in inputs {
+            hasher.update(input);
+        }
+        out.extend_from_slice(&hasher.finalize());
+        counter = counter.wrapping_add(1);
+    }
+    out.truncate(len);
+    out
+}
+
+/// HMAC_DRBG with SHA-256
+///
+/// State is wiped on drop. Generate calls fail once the reseed interval
+/// is exhausted, until [`HmacDrbg::reseed`] supplies fresh entropy.
+pub struct HmacDrbg {
+    key: [u8; 32],
+    value: [u8; 32],
+    reseed_counter: u64,
+    reseed_interval: u64,
+}
+
+impl HmacDrbg {
+    /// Instantiates the DRBG from entropy, a nonce and a personalization string
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8], reseed_interval: u64) -> Self {
+        let mut drbg = Self {
+            key: [0x00; 32],
+            value: [0x01; 32],
+            reseed_counter: 1,
+            reseed_interval: reseed_interval.max(1),
+        };
+        drbg.update(&[entropy, nonce, personalization]);
+        drbg
+    }
+
+    /// Mixes fresh entropy and optional additional input into the state
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) {
+        self.update(&[entropy, additional_input]);
+        self.reseed_counter = 1;
+    }
+
+    /// Returns true once the next generate call would be refused
+    pub fn needs_reseed(&self) -> bool {
+        self.reseed_counter > self.reseed_interval
+    }
+
+    /// Number of generate calls since the last (re)seed, plus one
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    /// Fills `dest` with output bound to `additional_input`
+    pub fn generate(&mut self, dest: &mut [u8], additional_input: &[u8]) -> Result<()> {
+        if dest.len() > MAX_REQUEST_BYTES {
+            return Err(FskcError::InvalidDataSize(dest.len()));
+        }
+        if self.needs_reseed() {
+            return Err(FskcError::EntropyError("DRBG reseed required".into()));
+        }
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+        for chunk in dest.chunks_mut(32) {
+            self.value = self.mac(&[&self.value]);
+            chunk.copy_from_slice(&self.value[..chunk.len()]);
+        }
+        self.update(&[additional_input]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// The HMAC_DRBG update function over the concatenation of `provided`
+    fn update(&mut self, provided: &[&[u8]]) {
+        let empty = provided.iter().all(|part| part.is_empty());
+        for separator in [0x00u8, 0x01] {
+            if separator == 0x01 && empty {
+                break;
+            }
+            let separator = [separator];
+            let mut parts = vec![&self.value[..], &separator[..]];
+            parts.extend_from_slice(provided);
+            self.key = self.mac(&parts);
+            self.value = self.mac(&[&self.value]);
+        }
+    }
+
+    fn mac(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut mac = HmacSha256::new_from_slice(&self.key)
+            .expect("HMAC accepts keys of any length");
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().into()
+    }
+}
+
+impl Drop for HmacDrbg {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.value.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_cavp_vector() {
+        // NIST CAVP HMAC_DRBG, SHA-256, no prediction resistance, COUNT = 0
+        let mut drbg = HmacDrbg::new(
+            &hex("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488"),
+            &hex("659ba96c601dc69fc902940805ec0ca8"),
+            &[],
+            u64::MAX,
+        );
+        let mut out = [0u8; 128];
+        drbg.generate(&mut out, &[]).unwrap();
+        drbg.generate(&mut out, &[]).unwrap();
+        assert_eq!(out.to_vec(), hex(CAVP_RETURNED_BITS));
+    }
+
+    #[test]
+    fn test_reseed_and_additional_input() {
+        let mut drbg = HmacDrbg::new(&(0..32).collect::<Vec<u8>>(), &(32..48).collect::<Vec<u8>>(), b"fskc-test", 1);
+        let mut out = [0u8; 32];
+        drbg.generate(&mut out, &[]).unwrap();
+
+        // The interval of one call is spent
+        assert!(drbg.needs_reseed());
+        assert!(drbg.generate(&mut out, &[]).is_err());
+
+        let mut drbg = HmacDrbg::new(&(0..32).collect::<Vec<u8>>(), &(32..48).collect::<Vec<u8>>(), b"fskc-test", 1);
+        drbg.reseed(&(48..80).collect::<Vec<u8>>(), b"reseed");
+        assert_eq!(drbg.reseed_counter(), 1);
+        drbg.generate(&mut out, b"extra").unwrap();
+        assert_eq!(out.to_vec(), hex("a846b4b82e1eda8003e8decef21a7d171d2f2ffb1dbf0e4b146ea7a348663354"));
+
+        assert!(drbg.generate(&mut vec![0u8; MAX_REQUEST_BYTES + 1], &[]).is_err());
+    }
+
+    #[test]
+    fn test_hash_df() {
+        assert_eq!(
+            hash_df(&[b"a", b"bc"], 48),
+            hex("d3317e0ef922883de460215a843cbb5b6bae1bc4799c8b472b8164926fa213a7c52d3b8aea03214a4475b68e7554007d")
+        );
+    }
+
+    const CAVP_RETURNED_BITS: &str = "e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89\
+        d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc1\
+        07694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668\
+        961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8";
+}
@@ -0,0 +1,325 @@
+//! Min-entropy estimators after NIST SP 800-90B section 6.3
+//!
+//! The most-common-value and t-tuple estimators accept samples from any
+//! alphabet. The collision, Markov and compression estimators are defined
+//! for binary data and take bits, one per byte; [`estimate`] feeds them the
+//! bits of byte samples, most significant first. Each estimator bounds the
+//! probability of the likeliest outcome from above at 99% confidence, so
+//! the resulting entropy errs low.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// z-value of the 99% upper confidence bound
+const Z_99: f64 = 2.576;
+
+/// Occurrences the most common t-tuple needs before it is counted
+const TUPLE_CUTOFF: usize = 35;
+/// Longest tuple considered, bounding the work on degenerate input
+const MAX_TUPLE: usize = 32;
+
+/// Compression estimator block size, in bits
+const BLOCK_BITS: usize = 6;
+/// Compression estimator dictionary size, in blocks
+const DICTIONARY_BLOCKS: usize = 1000;
+/// Compression estimator test blocks, bounding the work on long input
+const TEST_BLOCKS: usize = 10_000;
+
+/// Results of every estimator over one byte stream, in bits per bit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinEntropyEstimate {
+    pub most_common_value: f64,
+    pub collision: f64,
+    pub markov: f64,
+    pub compression: f64,
+    pub t_tuple: f64,
+}
+
+impl MinEntropyEstimate {
+    /// The lowest of the estimates, in bits of min-entropy per bit
+    pub fn min_entropy_per_bit(&self) -> f64 {
+        [self.most_common_value, self.collision, self.markov, self.compression, self.t_tuple]
+            .into_iter()
+            .fold(1.0, f64::min)
+    }
+}
+
+/// Runs every estimator over byte samples
+pub fn estimate(samples: &[u8]) -> MinEntropyEstimate {
+    let bits = to_bits(samples);
+    MinEntropyEstimate {
+        most_common_value: most_common_value(samples) / 8.0,
+        collision: collision(&bits),
+        markov: markov(&bits),
+        compression: compression(&bits),
+        t_tuple: t_tuple(samples) / 8.0,
+    }
+}
+
+/// Splits bytes into bits, most significant first
+pub fn to_bits(samples: &[u8]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+        .collect()
+}
+
+/// Most-common-value estimate, in bits per sample
+pub fn most_common_value<T: Hash + Eq>(samples: &[T]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let mut counts = HashMap::new();
+    for sample in samples {
+        *counts.entry(sample).or_insert(0usize) += 1;
+    }
+    let most_common = counts.values().copied().max().unwrap_or(0);
+    let p = most_common as f64 / samples.len() as f64;
+    entropy_bits(upper_bound(p, samples.len()))
+}
+
+/// t-tuple estimate, in bits per sample
+pub fn t_tuple<T: Hash + Eq>(samples: &[T]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+
+    // Single samples always count, so short inputs fall back to the MCV
+    let mut p_max = 0.0f64;
+    for t in 1..=MAX_TUPLE.min(samples.len()) {
+        let mut counts: HashMap<&[T], usize> = HashMap::new();
+        for tuple in samples.windows(t) {
+            *counts.entry(tuple).or_insert(0) += 1;
+        }
+        let most_common = counts.values().copied().max().unwrap_or(0);
+        if t > 1 && most_common < TUPLE_CUTOFF {
+            break;
+        }
+        let p = most_common as f64 / (samples.len() - t + 1) as f64;
+        p_max = p_max.max(p.powf(1.0 / t as f64));
+    }
+    entropy_bits(upper_bound(p_max, samples.len()))
+}
+
+/// Collision estimate over bits, in bits per bit
+pub fn collision(bits: &[u8]) -> f64 {
+    // Samples until the first repeat: two if a pair matches, else three
+    let mut times = Vec::new();
+    let mut i = 0;
+    while i + 2 < bits.len() || (i + 1 < bits.len() && bits[i] == bits[i + 1]) {
+        let time = if bits[i] & 1 == bits[i + 1] & 1 { 2 } else { 3 };
+        times.push(time as f64);
+        i += time;
+    }
+    if times.len() < 2 {
+        return 0.0;
+    }
+    let bound = lower_mean_bound(&times, 1.0);
+
+    // The expected collision time is 2 + 2p(1 - p) for binary data
+    let pq = ((bound - 2.0) / 2.0).clamp(0.0, 0.25);
+    let p = (1.0 + (1.0 - 4.0 * pq).sqrt()) / 2.0;
+    entropy_bits(p)
+}
+
+/// Markov estimate over bits, in bits per bit
+pub fn markov(bits: &[u8]) -> f64 {
+    if bits.len() < 2 {
+        return 0.0;
+    }
+    let ones = bits.iter().filter(|&&b| b & 1 == 1).count();
+    let p1 = ones as f64 / bits.len() as f64;
+    let p0 = 1.0 - p1;
+
+    let mut counts = [[0usize; 2]; 2];
+    for pair in bits.windows(2) {
+        counts[(pair[0] & 1) as usize][(pair[1] & 1) as usize] += 1;
+    }
+    let transition = |from: usize, to: usize| {
+        let total = counts[from][0] + counts[from][1];
+        if total == 0 { 0.0 } else { counts[from][to] as f64 / total as f64 }
+    };
+    let (p00, p01, p10, p11) = (
+        transition(0, 0).log2(),
+        transition(0, 1).log2(),
+        transition(1, 0).log2(),
+        transition(1, 1).log2(),
+    );
+    let (p0, p1) = (p0.log2(), p1.log2());
+
+    // log2 probabilities of the likeliest 128-bit sequences
+    let likeliest = [
+        p0 + 127.0 * p00,
+        p0 + 64.0 * p01 + 63.0 * p10,
+        p0 + p01 + 126.0 * p11,
+        p1 + p10 + 126.0 * p00,
+        p1 + 64.0 * p10 + 63.0 * p01,
+        p1 + 127.0 * p11,
+    ]
+    .into_iter()
+    .fold(f64::NEG_INFINITY, f64::max);
+    (-likeliest / 128.0).clamp(0.0, 1.0)
+}
+
+/// Compression estimate over bits, in bits per bit
+///
+/// Only the first [`TEST_BLOCKS`] blocks after the dictionary are tested;
+/// later bits do not affect the estimate.
+pub fn compression(bits: &[u8]) -> f64 {
+    let mut blocks: Vec<usize> = bits
+        .chunks_exact(BLOCK_BITS)
+        .map(|block| block.iter().fold(0, |acc, &b| acc << 1 | (b & 1) as usize))
+        .collect();
+
+    // Short inputs split their blocks evenly between dictionary and test
+    let dictionary_len = DICTIONARY_BLOCKS.min(blocks.len() / 2);
+    let tested = (blocks.len() - dictionary_len).min(TEST_BLOCKS);
+    blocks.truncate(dictionary_len + tested);
+    if dictionary_len == 0 || tested < 2 {
+        return 0.0;
+    }
+
+    // Maurer's universal statistic: log distance to the previous occurrence
+    let mut last_seen = [0usize; 1 << BLOCK_BITS];
+    for (i, &block) in blocks[..dictionary_len].iter().enumerate() {
+        last_seen[block] = i + 1;
+    }
+    let mut distances = Vec::with_capacity(tested);
+    for i in dictionary_len + 1..=blocks.len() {
+        let block = blocks[i - 1];
+        distances.push(((i - last_seen[block]) as f64).log2());
+        last_seen[block] = i;
+    }
+    let bound = lower_mean_bound(&distances, 0.5907);
+
+    let expected = |p: f64| {
+        let q = (1.0 - p) / ((1 << BLOCK_BITS) - 1) as f64;
+        statistic(p, blocks.len(), dictionary_len) + ((1 << BLOCK_BITS) - 1) as f64 * statistic(q, blocks.len(), dictionary_len)
+    };
+    let p = solve_decreasing(expected, bound, 1.0 / (1 << BLOCK_BITS) as f64, 1.0);
+    entropy_bits(p) / BLOCK_BITS as f64
+}
+
+/// Expected statistic contribution of one block value of probability `z`
+fn statistic(z: f64, blocks: usize, dictionary_len: usize) -> f64 {
+    // Sums log2(u) over every distance u, weighted by how many test
+    // positions can see it as a repeat or as the first occurrence
+    let mut total = 0.0;
+    let mut miss = 1.0;
+    for u in 1..=blocks {
+        let repeats = (blocks - u.max(dictionary_len)) as f64;
+        let first = if u > dictionary_len { z } else { 0.0 };
+        total += (u as f64).log2() * miss * (z * z * repeats + first);
+        miss *= 1.0 - z;
+    }
+    total / (blocks - dictionary_len) as f64
+}
+
+/// Lower 99% confidence bound on the mean of `values`
+fn lower_mean_bound(values: &[f64], scale: f64) -> f64 {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    mean - Z_99 * scale * variance.sqrt() / n.sqrt()
+}
+
+/// Upper 99% confidence bound on a proportion estimated from `n` samples
+fn upper_bound(p: f64, n: usize) -> f64 {
+    (p + Z_99 * (p * (1.0 - p) / (n - 1) as f64).sqrt()).min(1.0)
+}
+
+/// Finds `p` in `[low, high]` where the decreasing `f` meets `target`
+fn solve_decreasing(f: impl Fn(f64) -> f64, target: f64, mut low: f64, mut high: f64) -> f64 {
+    if target >= f(low) {
+        return low;
+    }
+    if target <= f(high) {
+        return high;
+    }
+    for _ in 0..64 {
+        let mid = (low + high) / 2.0;
+        if f(mid) > target {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Min-entropy of an outcome with probability `p`, in bits
+fn entropy_bits(p: f64) -> f64 {
+    (-p.log2()).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_random_bytes_score_high() {
+        let mut bytes = vec![0u8; 1 << 16];
+        ChaCha20Rng::seed_from_u64(12345).fill_bytes(&mut bytes);
+
+        let estimate = estimate(&bytes);
+        for value in [estimate.most_common_value, estimate.collision, estimate.markov, estimate.t_tuple] {
+            assert!(value > 0.85 && value <= 1.0, "{:?}", estimate);
+        }
+
+        // The compression statistic barely moves near uniform, so its
+        // confidence bound costs the most
+        assert!(estimate.compression > 0.7, "{:?}", estimate);
+        assert_eq!(estimate.min_entropy_per_bit(), estimate.compression);
+    }
+
+    #[test]
+    fn test_constant_bytes_score_zero() {
+        let zeros = estimate(&[0; 4096]);
+        assert_eq!(zeros.min_entropy_per_bit(), 0.0);
+        for value in [zeros.most_common_value, zeros.collision, zeros.markov, zeros.compression, zeros.t_tuple] {
+            assert!(value < 1e-9, "{:?}", zeros);
+        }
+
+        let stuck = estimate(&[0x42; 4096]);
+        assert_eq!(stuck.most_common_value, 0.0);
+        assert!(stuck.min_entropy_per_bit() < 0.01);
+    }
+
+    #[test]
+    fn test_patterns_are_caught() {
+        // Alternating bits fool the collision test but not the Markov test
+        let alternating = estimate(&[0x55; 4096]);
+        assert_eq!(alternating.collision, 1.0);
+        assert!(alternating.markov < 0.01);
+
+        // A repeating byte sequence has a flat histogram but short tuples
+        let cycle: Vec<u8> = (0..=255u8).cycle().take(1 << 14).collect();
+        assert!(most_common_value(&cycle) > 7.0);
+        assert!(t_tuple(&cycle) < 1.0);
+    }
+
+    #[test]
+    fn test_biased_bits() {
+        // Each bit is 1 with probability 0.9: min-entropy -log2(0.9) ≈ 0.152
+        let mut rng = ChaCha20Rng::seed_from_u64(67890);
+        let bits: Vec<u8> = (0..1 << 16).map(|_| rng.gen_bool(0.9) as u8).collect();
+
+        for value in [most_common_value(&bits), markov(&bits), collision(&bits), t_tuple(&bits)] {
+            assert!(value > 0.1 && value < 0.16, "{}", value);
+        }
+        assert!(compression(&bits) < 0.3);
+    }
+
+    #[test]
+    fn test_compression_tests_bounded_prefix() {
+        let mut bytes = vec![0u8; 1 << 16];
+        ChaCha20Rng::seed_from_u64(24680).fill_bytes(&mut bytes);
+        let bits = to_bits(&bytes);
+
+        let prefix = (DICTIONARY_BLOCKS + TEST_BLOCKS) * BLOCK_BITS;
+        assert!(bits.len() > prefix);
+        assert_eq!(compression(&bits), compression(&bits[..prefix]));
+    }
+}
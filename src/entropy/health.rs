@@ -0,0 +1,259 @@
+//! Continuous health tests for entropy sources
+//!
+//! Implements the repetition count test (RCT) and adaptive proportion test
+//! (APT) of NIST SP 800-90B section 4.4, treating every output byte as one
+//! sample. The same tests run over the startup samples drawn from a source
+//! before its output is first used.
+
+use std::fmt;
+
+/// Parameters of the health tests
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Assessed min-entropy of one byte sample, in bits
+    pub min_entropy: f64,
+    /// False positive probability of each test is 2^-n
+    pub false_positive_exponent: u32,
+    /// Adaptive proportion test window, in samples
+    pub window: usize,
+    /// Samples tested and discarded before a source is first used
+    pub startup_samples: usize,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            min_entropy: 1.0,             // Conservative for physical sensors
+            false_positive_exponent: 20,  // α = 2^-20
+            window: 512,                  // Non-binary window of SP 800-90B
+            startup_samples: 1024,
+        }
+    }
+}
+
+impl HealthConfig {
+    /// Run length at which the repetition count test fails
+    pub fn repetition_cutoff(&self) -> usize {
+        let entropy = self.min_entropy.clamp(f64::MIN_POSITIVE, 8.0);
+        1 + (self.false_positive_exponent as f64 / entropy).ceil() as usize
+    }
+
+    /// Count within a window at which the adaptive proportion test fails
+    pub fn proportion_cutoff(&self) -> usize {
+        let p = 2f64.powf(-self.min_entropy.clamp(0.0, 8.0));
+        if p >= 1.0 {
+            return self.window + 1;
+        }
+        let alpha = 2f64.powi(-(self.false_positive_exponent as i32));
+
+        // 1 + CRITBINOM(W, p, 1 - α), walking the binomial distribution
+        let n = self.window;
+        let ratio = p / (1.0 - p);
+        let mut pmf = (1.0 - p).powi(n as i32);
+        let mut cdf = pmf;
+        let mut k = 0;
+        while cdf < 1.0 - alpha && k < n {
+            pmf *= (n - k) as f64 / (k + 1) as f64 * ratio;
+            cdf += pmf;
+            k += 1;
+        }
+        1 + k
+    }
+}
+
+/// Reason a source was quarantined
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthFailure {
+    /// The same byte repeated `run` times in a row
+    RepetitionCount { value: u8, run: usize },
+    /// The first byte of a window recurred `count` times within it
+    AdaptiveProportion { value: u8, count: usize },
+    /// The source itself reported an error
+    SourceError(String),
+}
+
+impl fmt::Display for HealthFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthFailure::RepetitionCount { value, run } => {
+                write!(f, "repetition count test failed: {:#04x} repeated {} times", value, run)
+            }
+            HealthFailure::AdaptiveProportion { value, count } => {
+                write!(f, "adaptive proportion test failed: {:#04x} seen {} times in one window", value, count)
+            }
+            HealthFailure::SourceError(message) => write!(f, "source error: {}", message),
+        }
+    }
+}
+
+/// Health of a single source
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthStatus {
+    /// The source passes its health tests and contributes output
+    Healthy,
+    /// The source failed a test and no longer contributes output
+    Quarantined(HealthFailure),
+}
+
+/// Health report for one source of a combined pool
+#[derive(Debug, Clone)]
+pub struct SourceHealth {
+    /// Description of the source
+    pub description: String,
+    /// Current health of the source
+    pub status: HealthStatus,
+    /// Number of samples run through the tests, including startup samples
+    pub samples_tested: u64,
+}
+
+/// Running state of the health tests for one source
+#[derive(Debug, Clone)]
+pub(crate) struct HealthMonitor {
+    repetition_cutoff: usize,
+    proportion_cutoff: usize,
+    window: usize,
+    last: Option<u8>,
+    run: usize,
+    window_value: u8,
+    window_count: usize,
+    window_seen: usize,
+    samples_tested: u64,
+    failure: Option<HealthFailure>,
+}
+
+impl HealthMonitor {
+    pub(crate) fn new(config: &HealthConfig) -> Self {
+        Self {
+            repetition_cutoff: config.repetition_cutoff(),
+            proportion_cutoff: config.proportion_cutoff(),
+            window: config.window.max(1),
+            last: None,
+            run: 0,
+            window_value: 0,
+            window_count: 0,
+            window_seen: 0,
+            samples_tested: 0,
+            failure: None,
+        }
+    }
+
+    pub(crate) fn is_healthy(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    pub(crate) fn status(&self) -> HealthStatus {
+        match &self.failure {
+            None => HealthStatus::Healthy,
+            Some(failure) => HealthStatus::Quarantined(failure.clone()),
+        }
+    }
+
+    pub(crate) fn samples_tested(&self) -> u64 {
+        self.samples_tested
+    }
+
+    pub(crate) fn quarantine(&mut self, failure: HealthFailure) {
+        self.failure.get_or_insert(failure);
+    }
+
+    /// Runs every sample through both tests, quarantining on the first failure
+    pub(crate) fn test(&mut self, samples: &[u8]) -> bool {
+        for &sample in samples {
+            if !self.is_healthy() {
+                return false;
+            }
+            self.samples_tested += 1;
+            self.repetition_count(sample);
+            self.adaptive_proportion(sample);
+        }
+        self.is_healthy()
+    }
+
+    fn repetition_count(&mut self, sample: u8) {
+        if self.last == Some(sample) {
+            self.run += 1;
+            if self.run >= self.repetition_cutoff {
+                self.quarantine(HealthFailure::RepetitionCount { value: sample, run: self.run });
+            }
+        } else {
+            self.last = Some(sample);
+            self.run = 1;
+        }
+    }
+
+    fn adaptive_proportion(&mut self, sample: u8) {
+        if self.window_seen == 0 {
+            self.window_value = sample;
+            self.window_count = 1;
+        } else if sample == self.window_value {
+            self.window_count += 1;
+            if self.window_count >= self.proportion_cutoff {
+                self.quarantine(HealthFailure::AdaptiveProportion {
+                    value: sample,
+                    count: self.window_count,
+                });
+            }
+        }
+        self.window_seen = (self.window_seen + 1) % self.window;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_cutoffs_match_sp800_90b() {
+        let config = HealthConfig::default();
+        assert_eq!(config.repetition_cutoff(), 21);
+        assert_eq!(config.proportion_cutoff(), 311);
+
+        // Table 2 of SP 800-90B for the 512-sample window
+        for (min_entropy, cutoff) in [(0.5, 410), (2.0, 177), (4.0, 62), (8.0, 13)] {
+            let config = HealthConfig { min_entropy, ..HealthConfig::default() };
+            assert_eq!(config.proportion_cutoff(), cutoff, "H = {}", min_entropy);
+        }
+        let full = HealthConfig { min_entropy: 8.0, ..HealthConfig::default() };
+        assert_eq!(full.repetition_cutoff(), 4);
+    }
+
+    #[test]
+    fn test_random_bytes_pass() {
+        let mut bytes = vec![0u8; 1 << 16];
+        ChaCha20Rng::seed_from_u64(12345).fill_bytes(&mut bytes);
+
+        let mut monitor = HealthMonitor::new(&HealthConfig::default());
+        assert!(monitor.test(&bytes));
+        assert_eq!(monitor.status(), HealthStatus::Healthy);
+        assert_eq!(monitor.samples_tested(), 1 << 16);
+    }
+
+    #[test]
+    fn test_stuck_source_fails_repetition_count() {
+        let mut monitor = HealthMonitor::new(&HealthConfig::default());
+        assert!(!monitor.test(&[0x42; 64]));
+        assert_eq!(
+            monitor.status(),
+            HealthStatus::Quarantined(HealthFailure::RepetitionCount { value: 0x42, run: 21 })
+        );
+
+        // Quarantine is sticky
+        assert!(!monitor.test(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_biased_source_fails_adaptive_proportion() {
+        // Never repeats, but half of every window is the same byte
+        let biased: Vec<u8> = (0..2048).map(|i| if i % 2 == 0 { 7 } else { (i % 251) as u8 }).collect();
+        let config = HealthConfig { min_entropy: 4.0, ..HealthConfig::default() };
+
+        let mut monitor = HealthMonitor::new(&config);
+        assert!(!monitor.test(&biased));
+        assert!(matches!(
+            monitor.status(),
+            HealthStatus::Quarantined(HealthFailure::AdaptiveProportion { value: 7, .. })
+        ));
+    }
+}
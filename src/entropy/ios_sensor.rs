@@ -86,12 +86,15 @@ impl AccelerometerSource {
         let mut total_entropy = 0.0;
         let mut total_snr = 0.0;
         let mut total_consistency = 0.0;
+        let mut min_entropy = f64::INFINITY;
         let mut count = 0;
 
         for reading in recent {
             total_entropy += reading.quality.shannon_entropy;
             total_snr += reading.quality.signal_to_noise;
             total_consistency += reading.quality.temporal_consistency;
+            // The weakest reading bounds the min-entropy
+            min_entropy = min_entropy.min(reading.quality.min_entropy_per_bit);
             count += 1;
         }
 
@@ -101,6 +104,7 @@ impl AccelerometerSource {
                 sample_rate: self.sample_rate,
                 signal_to_noise: total_snr / count as f64,
                 temporal_consistency: total_consistency / count as f64,
+                min_entropy_per_bit: min_entropy,
             };
         }
     }
@@ -172,12 +176,15 @@ impl BarometerSource {
         let mut total_entropy = 0.0;
         let mut total_snr = 0.0;
         let mut total_consistency = 0.0;
+        let mut min_entropy = f64::INFINITY;
         let mut count = 0;
 
         for reading in recent {
             total_entropy += reading.quality.shannon_entropy;
             total_snr += reading.quality.signal_to_noise;
             total_consistency += reading.quality.temporal_consistency;
+            // The weakest reading bounds the min-entropy
+            min_entropy = min_entropy.min(reading.quality.min_entropy_per_bit);
             count += 1;
         }
 
@@ -187,6 +194,7 @@ impl BarometerSource {
                 sample_rate: self.sample_rate,
                 signal_to_noise: total_snr / count as f64,
                 temporal_consistency: total_consistency / count as f64,
+                min_entropy_per_bit: min_entropy,
             };
         }
     }
@@ -280,6 +288,7 @@ impl IosSensorEntropy {
             sample_rate: accel_quality.sample_rate,
             signal_to_noise: (accel_quality.signal_to_noise + baro_quality.signal_to_noise) / 2.0,
             temporal_consistency: (accel_quality.temporal_consistency + baro_quality.temporal_consistency) / 2.0,
+            min_entropy_per_bit: accel_quality.min_entropy_per_bit.min(baro_quality.min_entropy_per_bit),
         };
     }
 }
@@ -319,6 +328,7 @@ mod tests {
                 sample_rate: 100.0,
                 signal_to_noise: 10.0,
                 temporal_consistency: 0.9,
+                min_entropy_per_bit: 0.8,
             },
         }
     }
@@ -1,13 +1,32 @@
-use crate::Result;
+use crate::{FskcError, Result, SecretBytes};
 use rand::{RngCore, Error as RngError, CryptoRng};
 use std::sync::{Arc, Mutex};
-use getrandom::Error as GetRandomError;
+use std::thread;
+use std::time::Instant;
 
 pub mod sensor;
 pub mod ios_sensor;
+pub mod health;
+pub mod estimators;
+pub mod drbg;
+pub mod accounting;
+pub mod replay;
 
 pub use sensor::{Sensor, SensorConfig, EntropyQuality};
 pub use ios_sensor::{IosSensorEntropy, SensorReading, AccelerometerSource, BarometerSource};
+pub use health::{HealthConfig, HealthFailure, HealthStatus, SourceHealth};
+pub use accounting::{GateConfig, GatePolicy, SourceStats};
+pub use replay::{Recording, RecordedSample, ReplaySensor};
+
+use health::HealthMonitor;
+use accounting::SourceAccount;
+use drbg::{HmacDrbg, MAX_REQUEST_BYTES, SECURITY_STRENGTH};
+
+/// Generate requests served between automatic reseeds by default
+pub const DEFAULT_RESEED_INTERVAL: u64 = 1 << 16;
+
+/// Size of the conditioned pool carried between sampling rounds
+const POOL_BYTES: usize = 64;
 
 /// Represents a source of entropy
 pub trait EntropySource: Send + Sync {
@@ -16,6 +35,14 @@ pub trait EntropySource: Send + Sync {
     
     /// Returns a description of the entropy source
     fn description(&self) -> &str;
+
+    /// Declared min-entropy of one output byte, in bits
+    ///
+    /// Sources returning `None` are credited at a rate estimated from
+    /// their startup samples.
+    fn entropy_per_byte(&self) -> Option<f64> {
+        None
+    }
 }
 
 
@@ -23,6 +50,7 @@ pub trait EntropySource: Send + Sync {
 pub struct RngEntropy<R: RngCore> {
     rng: R,
     description: String,
+    entropy_per_byte: Option<f64>,
 }
 
 impl<R: RngCore> RngEntropy<R> {
@@ -30,8 +58,15 @@ impl<R: RngCore> RngEntropy<R> {
         Self {
             rng,
             description: description.into(),
+            entropy_per_byte: None,
         }
     }
+
+    /// Declares the min-entropy of each output byte instead of estimating it
+    pub fn with_entropy_per_byte(mut self, bits: f64) -> Self {
+        self.entropy_per_byte = Some(bits);
+        self
+    }
 }
 
 impl<R: RngCore + Send + Sync> EntropySource for RngEntropy<R> {
@@ -43,13 +78,21 @@ impl<R: RngCore + Send + Sync> EntropySource for RngEntropy<R> {
     fn description(&self) -> &str {
         &self.description
     }
+
+    fn entropy_per_byte(&self) -> Option<f64> {
+        self.entropy_per_byte
+    }
 }
 
 /// Physical measurement-based entropy source
+///
+/// Each measurement is handed out once; the source fails once they are
+/// used up.
 pub struct PhysicalEntropy {
     data: Vec<u8>,
     position: usize,
     description: String,
+    entropy_per_byte: Option<f64>,
 }
 
 impl PhysicalEntropy {
@@ -58,9 +101,16 @@ impl PhysicalEntropy {
             data,
             position: 0,
             description: description.into(),
+            entropy_per_byte: None,
         }
     }
 
+    /// Declares the min-entropy of each byte instead of estimating it
+    pub fn with_entropy_per_byte(mut self, bits: f64) -> Self {
+        self.entropy_per_byte = Some(bits);
+        self
+    }
+
     /// Creates a LIGO gravitational wave data source
     pub fn from_ligo_data(data: Vec<u8>) -> Self {
         Self::new(data, "LIGO Gravitational Wave Data")
@@ -74,28 +124,49 @@ impl PhysicalEntropy {
 
 impl EntropySource for PhysicalEntropy {
     fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<()> {
-        for byte in dest.iter_mut() {
-            if self.position >= self.data.len() {
-                self.position = 0; // Wrap around
-            }
-            *byte = self.data[self.position];
-            self.position += 1;
+        // Replaying the measurements would only repeat their entropy
+        let end = self.position + dest.len();
+        if end > self.data.len() {
+            return Err(FskcError::EntropyError(format!("{} exhausted", self.description)));
         }
+        dest.copy_from_slice(&self.data[self.position..end]);
+        self.position = end;
         Ok(())
     }
 
     fn description(&self) -> &str {
         &self.description
     }
+
+    fn entropy_per_byte(&self) -> Option<f64> {
+        self.entropy_per_byte
+    }
 }
 
 /// Combines multiple entropy sources including physical sensors
+///
+/// Every source and sensor runs through continuous health tests. One that
+/// fails is quarantined and no longer contributes output. Raw output of
+/// the healthy sources is conditioned with `Hash_df` and seeds an
+/// HMAC-DRBG, which produces every byte handed out. Each source is credited
+/// with the entropy it contributes, and the DRBG only (re)seeds once the
+/// credit since its last seed reaches the threshold of the [`GateConfig`].
 pub struct CombinedEntropy {
     sources: Vec<Box<dyn EntropySource>>,
     sensors: Vec<Box<dyn Sensor>>,
-    buffer: Vec<u8>,
-    position: usize,
+    source_health: Vec<HealthMonitor>,
+    sensor_health: Vec<HealthMonitor>,
+    source_accounts: Vec<SourceAccount>,
+    sensor_accounts: Vec<SourceAccount>,
+    pool: SecretBytes,
+    fresh_bits: f64,
+    drbg: Option<HmacDrbg>,
+    personalization: Vec<u8>,
+    reseed_interval: u64,
+    prediction_resistance: bool,
     sensor_config: SensorConfig,
+    health_config: HealthConfig,
+    gate_config: GateConfig,
 }
 
 impl CombinedEntropy {
@@ -103,16 +174,29 @@ impl CombinedEntropy {
         Self {
             sources: Vec::new(),
             sensors: Vec::new(),
-            buffer: Vec::new(),
-            position: 0,
+            source_health: Vec::new(),
+            sensor_health: Vec::new(),
+            source_accounts: Vec::new(),
+            sensor_accounts: Vec::new(),
+            pool: SecretBytes::default(),
+            fresh_bits: 0.0,
+            drbg: None,
+            personalization: Vec::new(),
+            reseed_interval: DEFAULT_RESEED_INTERVAL,
+            prediction_resistance: false,
             sensor_config: SensorConfig::default(),
+            health_config: HealthConfig::default(),
+            gate_config: GateConfig::default(),
         }
     }
 
-    /// Add a sensor to the entropy pool
+    /// Add a sensor to the entropy pool, running its startup tests
     pub fn add_sensor<S: Sensor + 'static>(&mut self, mut sensor: S) -> Result<()> {
         sensor.start(&self.sensor_config)?;
+        let (monitor, account) = startup(&self.health_config, sensor.entropy_per_byte(), |dest| sensor.fill_entropy(dest));
         self.sensors.push(Box::new(sensor));
+        self.sensor_health.push(monitor);
+        self.sensor_accounts.push(account);
         Ok(())
     }
 
@@ -138,9 +222,68 @@ impl CombinedEntropy {
             .collect()
     }
 
-    /// Adds an entropy source to the combination
-    pub fn add_source<S: EntropySource + 'static>(&mut self, source: S) {
+    /// Adds an entropy source to the combination, running its startup tests
+    pub fn add_source<S: EntropySource + 'static>(&mut self, mut source: S) {
+        let (monitor, account) = startup(&self.health_config, source.entropy_per_byte(), |dest| source.fill_bytes(dest));
         self.sources.push(Box::new(source));
+        self.source_health.push(monitor);
+        self.source_accounts.push(account);
+    }
+
+    /// Replaces the health test parameters
+    ///
+    /// Every source restarts its tests, including its startup tests, so
+    /// this also releases sources from quarantine if they now pass. The
+    /// accounts of the sources restart with them.
+    pub fn set_health_config(&mut self, config: HealthConfig) {
+        self.health_config = config;
+        (self.source_health, self.source_accounts) = self.sources
+            .iter_mut()
+            .map(|source| startup(&self.health_config, source.entropy_per_byte(), |dest| source.fill_bytes(dest)))
+            .unzip();
+        (self.sensor_health, self.sensor_accounts) = self.sensors
+            .iter_mut()
+            .map(|sensor| startup(&self.health_config, sensor.entropy_per_byte(), |dest| sensor.fill_entropy(dest)))
+            .unzip();
+    }
+
+    /// Replaces the credited-entropy gate on seeding
+    pub fn set_gate_config(&mut self, config: GateConfig) {
+        self.gate_config = config;
+    }
+
+    /// Reports the health of every source, followed by every sensor
+    pub fn health(&self) -> Vec<SourceHealth> {
+        let sources = self.sources.iter().map(|s| s.description()).zip(&self.source_health);
+        let sensors = self.sensors.iter().map(|s| s.description()).zip(&self.sensor_health);
+        sources
+            .chain(sensors)
+            .map(|(description, monitor)| SourceHealth {
+                description: description.to_string(),
+                status: monitor.status(),
+                samples_tested: monitor.samples_tested(),
+            })
+            .collect()
+    }
+
+    /// Reports the entropy accounting of every source, followed by every sensor
+    pub fn stats(&self) -> Vec<SourceStats> {
+        let sources = self.sources.iter().map(|s| s.description()).zip(self.source_health.iter().zip(&self.source_accounts));
+        let sensors = self.sensors.iter().map(|s| s.description()).zip(self.sensor_health.iter().zip(&self.sensor_accounts));
+        sources
+            .chain(sensors)
+            .map(|(description, (monitor, account))| account.stats(description, monitor.status()))
+            .collect()
+    }
+
+    /// Bits of min-entropy credited since the DRBG last seeded
+    pub fn credited_bits(&self) -> f64 {
+        self.fresh_bits
+    }
+
+    /// Returns true if at least one source is not quarantined
+    pub fn is_healthy(&self) -> bool {
+        self.source_health.iter().chain(&self.sensor_health).any(HealthMonitor::is_healthy)
     }
 
     /// Lists all entropy sources
@@ -148,49 +291,155 @@ impl CombinedEntropy {
         self.sources.iter().map(|s| s.description()).collect()
     }
 
-    fn ensure_buffer(&mut self, required: usize) -> Result<()> {
-        if self.position + required > self.buffer.len() {
-            // Reset buffer and position
-            self.buffer.clear();
-            self.position = 0;
-            
-            // Fill buffer with new entropy
-            let mut new_buffer = vec![0u8; 1024.max(required)];
-            self.fill_entropy(&mut new_buffer)?;
-            self.buffer = new_buffer;
+    /// Sets the personalization string of the DRBG
+    ///
+    /// The DRBG is instantiated afresh with it on the next request.
+    pub fn set_personalization(&mut self, personalization: &[u8]) {
+        self.personalization = personalization.to_vec();
+        self.drbg = None;
+    }
+
+    /// Sets how many requests the DRBG serves before it reseeds itself
+    pub fn set_reseed_interval(&mut self, interval: u64) {
+        self.reseed_interval = interval.max(1);
+        self.drbg = None;
+    }
+
+    /// Reseeds from the sources before every request when enabled
+    pub fn set_prediction_resistance(&mut self, enabled: bool) {
+        self.prediction_resistance = enabled;
+    }
+
+    /// Requests served since the DRBG was last seeded, plus one
+    ///
+    /// Zero until the first request instantiates the DRBG.
+    pub fn reseed_counter(&self) -> u64 {
+        self.drbg.as_ref().map_or(0, HmacDrbg::reseed_counter)
+    }
+
+    /// Reseeds the DRBG from the sources, mixing in `additional_input`
+    pub fn reseed(&mut self, additional_input: &[u8]) -> Result<()> {
+        if self.drbg.is_none() {
+            return self.instantiate();
+        }
+        let entropy = self.gather(SECURITY_STRENGTH)?;
+        if let Some(drbg) = &mut self.drbg {
+            drbg.reseed(&entropy, additional_input);
         }
         Ok(())
     }
 
-    fn fill_entropy(&mut self, dest: &mut [u8]) -> Result<()> {
-        // Initialize buffer with zeros
-        for byte in dest.iter_mut() {
-            *byte = 0;
+    /// Fills `dest` from the DRBG, binding `additional_input` to the output
+    ///
+    /// With `prediction_resistance` the DRBG reseeds from the sources
+    /// first, so the output stays unpredictable even to someone who has
+    /// learned the DRBG state.
+    pub fn generate(&mut self, dest: &mut [u8], additional_input: &[u8], prediction_resistance: bool) -> Result<()> {
+        let mut fresh = false;
+        if self.drbg.is_none() {
+            self.instantiate()?;
+            fresh = true;
+        }
+        if (prediction_resistance || self.prediction_resistance) && !fresh {
+            self.reseed(&[])?;
         }
 
-        // Collect entropy from standard sources
-        for source in &mut self.sources {
-            let mut source_bytes = vec![0u8; dest.len()];
-            source.fill_bytes(&mut source_bytes)?;
-            
-            // XOR with existing buffer
-            for (buf_byte, src_byte) in dest.iter_mut().zip(source_bytes.iter()) {
-                *buf_byte ^= src_byte;
+        for chunk in dest.chunks_mut(MAX_REQUEST_BYTES) {
+            if self.drbg.as_ref().is_none_or(HmacDrbg::needs_reseed) {
+                self.reseed(&[])?;
+            }
+            if let Some(drbg) = &mut self.drbg {
+                drbg.generate(chunk, additional_input)?;
             }
         }
+        Ok(())
+    }
 
-        // Collect entropy from sensors
-        for sensor in &mut self.sensors {
-            let mut sensor_bytes = vec![0u8; dest.len()];
-            sensor.fill_entropy(&mut sensor_bytes)?;
-            
-            // XOR with existing buffer
-            for (buf_byte, src_byte) in dest.iter_mut().zip(sensor_bytes.iter()) {
-                *buf_byte ^= src_byte;
+    fn instantiate(&mut self) -> Result<()> {
+        // Entropy input and nonce both come from the sources
+        let seed = self.gather(SECURITY_STRENGTH * 3 / 2)?;
+        let (entropy, nonce) = seed.split_at(SECURITY_STRENGTH / 8);
+        self.drbg = Some(HmacDrbg::new(entropy, nonce, &self.personalization, self.reseed_interval));
+        Ok(())
+    }
+
+    /// Draws one round from every healthy source into the pool
+    ///
+    /// Returns the bits credited. Requests draw rounds as they need them;
+    /// collecting ahead of time banks entropy for the next seed.
+    pub fn collect(&mut self) -> Result<f64> {
+        let len = self.gate_config.round_bytes.max(1);
+        let mut raw = Vec::new();
+        let mut credited = 0.0;
+
+        // Collect samples from healthy standard sources
+        let sources = self.sources.iter_mut().zip(&mut self.source_health).zip(&mut self.source_accounts);
+        for ((source, monitor), account) in sources {
+            let mut samples = SecretBytes::zeroed(len);
+            if sample(monitor, &mut samples, |b| source.fill_bytes(b)) {
+                credited += account.credit(len);
+                raw.push(samples);
             }
         }
 
-        Ok(())
+        // Collect samples from healthy sensors
+        let sensors = self.sensors.iter_mut().zip(&mut self.sensor_health).zip(&mut self.sensor_accounts);
+        for ((sensor, monitor), account) in sensors {
+            let mut samples = SecretBytes::zeroed(len);
+            if sample(monitor, &mut samples, |b| sensor.fill_entropy(b)) {
+                credited += account.credit(len);
+                raw.push(samples);
+            }
+        }
+
+        if raw.is_empty() {
+            return Err(FskcError::EntropyError("No healthy entropy source".into()));
+        }
+        let mut inputs = vec![self.pool.as_bytes()];
+        inputs.extend(raw.iter().map(|samples| samples.as_bytes()));
+        self.pool = SecretBytes::new(drbg::hash_df(&inputs, POOL_BYTES));
+
+        // The pool cannot hold more entropy than its size
+        self.fresh_bits = (self.fresh_bits + credited).min((POOL_BYTES * 8) as f64);
+        Ok(credited)
+    }
+
+    /// Collects until the gate threshold is credited, then conditions the
+    /// pool into `bits` of seed material
+    fn gather(&mut self, bits: usize) -> Result<SecretBytes> {
+        let started = Instant::now();
+        let mut rounds = 0;
+        while self.fresh_bits < self.gate_config.threshold_bits {
+            if rounds >= self.gate_config.max_rounds {
+                match self.gate_config.policy {
+                    GatePolicy::Block { poll_interval, timeout } if started.elapsed() < timeout => {
+                        thread::sleep(poll_interval);
+                    }
+                    _ => return Err(self.insufficient("")),
+                }
+            }
+            // Exhausted sources are quarantined and earn no further credit,
+            // so a pool that runs dry refuses instead of waiting
+            match self.collect() {
+                Err(FskcError::EntropyError(reason)) => {
+                    return Err(self.insufficient(&format!("; {}", reason)));
+                }
+                result => result?,
+            };
+            rounds += 1;
+        }
+
+        let seed = SecretBytes::new(drbg::hash_df(&[self.pool.as_bytes()], bits / 8));
+        self.pool = SecretBytes::default();
+        self.fresh_bits = 0.0;
+        Ok(seed)
+    }
+
+    fn insufficient(&self, reason: &str) -> FskcError {
+        FskcError::EntropyError(format!(
+            "Insufficient entropy: {:.1} of {:.1} bits credited{}",
+            self.fresh_bits, self.gate_config.threshold_bits, reason
+        ))
     }
 }
 
@@ -214,33 +463,7 @@ impl RngCore for CombinedEntropy {
     }
 
     fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), RngError> {
-        if let Err(_) = self.ensure_buffer(dest.len()) {
-            return Err(RngError::from(GetRandomError::UNSUPPORTED));
-        }
-
-        // Copy from buffer to destination
-        let available = self.buffer.len() - self.position;
-        let needed = dest.len();
-        
-        if needed <= available {
-            dest.copy_from_slice(&self.buffer[self.position..self.position + needed]);
-            self.position += needed;
-        } else {
-            // Fill what we can from current buffer
-            dest[..available].copy_from_slice(&self.buffer[self.position..]);
-            
-            // Generate new entropy for the rest
-            self.buffer.clear();
-            self.position = 0;
-            let mut new_buffer = vec![0u8; 1024.max(needed - available)];
-            if let Err(_) = self.fill_entropy(&mut new_buffer) {
-                return Err(RngError::from(GetRandomError::UNSUPPORTED));
-            }
-            
-            dest[available..].copy_from_slice(&new_buffer[..needed - available]);
-            self.buffer = new_buffer;
-            self.position = needed - available;
-        }
+        self.generate(dest, &[], false)?;
         Ok(())
     }
 }
@@ -248,6 +471,36 @@ impl RngCore for CombinedEntropy {
 // Mark CombinedEntropy as cryptographically secure
 impl CryptoRng for CombinedEntropy {}
 
+/// Runs the startup tests over samples that are then discarded, opening
+/// the source's account at its declared or estimated rate
+fn startup<F>(config: &HealthConfig, declared: Option<f64>, fill: F) -> (HealthMonitor, SourceAccount)
+where
+    F: FnOnce(&mut [u8]) -> Result<()>,
+{
+    let mut monitor = HealthMonitor::new(config);
+    let mut samples = SecretBytes::zeroed(config.startup_samples);
+    sample(&mut monitor, &mut samples, fill);
+    (monitor, SourceAccount::new(declared, &samples))
+}
+
+/// Fills `dest` from a healthy source and tests it, returning true if the
+/// output may be used
+fn sample<F>(monitor: &mut HealthMonitor, dest: &mut [u8], fill: F) -> bool
+where
+    F: FnOnce(&mut [u8]) -> Result<()>,
+{
+    if !monitor.is_healthy() {
+        return false;
+    }
+    match fill(dest) {
+        Ok(()) => monitor.test(dest),
+        Err(e) => {
+            monitor.quarantine(HealthFailure::SourceError(e.to_string()));
+            false
+        }
+    }
+}
+
 /// Builder for creating entropy configurations
 pub struct EntropyBuilder {
     combined: CombinedEntropy,
@@ -268,6 +521,30 @@ impl EntropyBuilder {
         self
     }
 
+    /// Configure health test parameters
+    pub fn with_health_config(mut self, config: HealthConfig) -> Self {
+        self.combined.set_health_config(config);
+        self
+    }
+
+    /// Configure the credited-entropy gate on seeding
+    pub fn with_gate_config(mut self, config: GateConfig) -> Self {
+        self.combined.set_gate_config(config);
+        self
+    }
+
+    /// Configure the DRBG personalization string
+    pub fn with_personalization(mut self, personalization: &[u8]) -> Self {
+        self.combined.set_personalization(personalization);
+        self
+    }
+
+    /// Reseed from the sources before every request
+    pub fn with_prediction_resistance(mut self) -> Self {
+        self.combined.set_prediction_resistance(true);
+        self
+    }
+
     /// Add an accelerometer sensor
     pub fn add_accelerometer(mut self) -> Self {
         let accel = sensor::Accelerometer::new();
@@ -301,6 +578,12 @@ impl EntropyBuilder {
         self
     }
 
+    /// Adds a custom entropy source
+    pub fn add_source<S: EntropySource + 'static>(mut self, source: S) -> Self {
+        self.combined.add_source(source);
+        self
+    }
+
     /// Adds a standard RNG source
     pub fn add_rng<R: RngCore + Send + Sync + 'static>(
         mut self,
@@ -344,6 +627,7 @@ mod tests {
     use super::*;
     use rand::SeedableRng;
     use rand_chacha::ChaCha20Rng;
+    use std::time::Duration;
 
     #[test]
     fn test_entropy_generation() {
@@ -399,6 +683,199 @@ mod tests {
         assert_ne!(bytes, bytes2);
     }
 
+    #[test]
+    fn test_correlated_sources_do_not_cancel() {
+        // Identical sources XORed together would produce all zeros
+        let mut entropy = CombinedEntropy::new();
+        entropy.add_source(RngEntropy::new(ChaCha20Rng::seed_from_u64(12345), "Copy 1"));
+        entropy.add_source(RngEntropy::new(ChaCha20Rng::seed_from_u64(12345), "Copy 2"));
+
+        let mut bytes = [0u8; 64];
+        entropy.fill_bytes(&mut bytes);
+        assert!(bytes.iter().any(|&b| b != 0));
+    }
+
+    #[test]
+    fn test_personalization_and_reseeding() {
+        let seeded = |personalization: &[u8]| {
+            let mut entropy = CombinedEntropy::new();
+            entropy.add_source(RngEntropy::new(ChaCha20Rng::seed_from_u64(12345), "ChaCha20"));
+            entropy.set_personalization(personalization);
+            entropy
+        };
+        let output = |entropy: &mut CombinedEntropy| {
+            let mut bytes = [0u8; 32];
+            entropy.fill_bytes(&mut bytes);
+            bytes
+        };
+
+        // Same sources and personalization give the same stream
+        assert_eq!(output(&mut seeded(b"device-a")), output(&mut seeded(b"device-a")));
+        assert_ne!(output(&mut seeded(b"device-a")), output(&mut seeded(b"device-b")));
+
+        let mut entropy = seeded(b"device-a");
+        assert_eq!(entropy.reseed_counter(), 0);
+        entropy.set_reseed_interval(2);
+        output(&mut entropy);
+        output(&mut entropy);
+        assert_eq!(entropy.reseed_counter(), 3);
+
+        // The interval is spent, so the next request reseeds first
+        output(&mut entropy);
+        assert_eq!(entropy.reseed_counter(), 2);
+
+        // Prediction resistance reseeds on every request
+        let mut bytes = [0u8; 32];
+        entropy.generate(&mut bytes, b"additional", true).unwrap();
+        assert_eq!(entropy.reseed_counter(), 2);
+        entropy.set_prediction_resistance(true);
+        output(&mut entropy);
+        assert_eq!(entropy.reseed_counter(), 2);
+
+        // Requests beyond the DRBG's limit are split
+        let mut large = vec![0u8; MAX_REQUEST_BYTES * 2 + 1];
+        entropy.set_prediction_resistance(false);
+        entropy.set_reseed_interval(DEFAULT_RESEED_INTERVAL);
+        assert!(entropy.try_fill_bytes(&mut large).is_ok());
+        assert_eq!(entropy.reseed_counter(), 4);
+    }
+
+    #[test]
+    fn test_output_is_gated_on_credited_entropy() {
+        // Half a bit per byte: 32 bits a round, so 8 rounds per seed
+        let mut entropy = CombinedEntropy::new();
+        entropy.add_source(
+            RngEntropy::new(ChaCha20Rng::seed_from_u64(12345), "Weak RNG").with_entropy_per_byte(0.5),
+        );
+        entropy.set_gate_config(GateConfig { max_rounds: 5, ..GateConfig::default() });
+
+        let mut bytes = [0u8; 32];
+        let err = entropy.try_fill_bytes(&mut bytes).unwrap_err();
+        assert!(err.to_string().contains("160.0 of 256.0 bits credited"));
+        assert_eq!(entropy.credited_bits(), 160.0);
+
+        // Banked credit carries over to the next request
+        assert!(entropy.try_fill_bytes(&mut bytes).is_ok());
+        assert_eq!(entropy.credited_bits(), 0.0);
+
+        let stats = entropy.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].entropy_per_byte, 0.5);
+        assert!(!stats[0].estimated);
+        assert_eq!(stats[0].bytes_drawn, 8 * 64);
+        assert_eq!(stats[0].credited_bits, 256.0);
+
+        // Blocking gives up after the timeout
+        entropy.set_gate_config(GateConfig {
+            threshold_bits: 1024.0,
+            max_rounds: 1,
+            policy: GatePolicy::Block {
+                poll_interval: Duration::from_millis(1),
+                timeout: Duration::from_millis(20),
+            },
+            ..GateConfig::default()
+        });
+        let started = Instant::now();
+        assert!(entropy.reseed(&[]).is_err());
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_exhausted_source_earns_no_credit() {
+        // Sixteen rounds of eight bytes after the startup samples
+        let mut data = vec![0u8; 1024 + 16 * 8];
+        ChaCha20Rng::seed_from_u64(12345).fill_bytes(&mut data);
+        let mut entropy = CombinedEntropy::new();
+        entropy.add_source(PhysicalEntropy::new(data, "Short Recording").with_entropy_per_byte(1.0));
+        entropy.set_gate_config(GateConfig { round_bytes: 8, max_rounds: 64, ..GateConfig::default() });
+
+        let mut bytes = [0u8; 32];
+        let err = entropy.try_fill_bytes(&mut bytes).unwrap_err();
+        assert!(err.to_string().contains("128.0 of 256.0 bits credited"));
+        assert_eq!(entropy.credited_bits(), 128.0);
+
+        // The recording is spent, so further requests add nothing
+        assert!(entropy.collect().is_err());
+        assert!(entropy.try_fill_bytes(&mut bytes).is_err());
+        assert_eq!(entropy.credited_bits(), 128.0);
+        assert_eq!(entropy.stats()[0].bytes_drawn, 16 * 8);
+        assert!(!entropy.is_healthy());
+    }
+
+    #[test]
+    fn test_rates_are_estimated_from_startup_samples() {
+        let mut entropy = CombinedEntropy::new();
+        entropy.add_source(RngEntropy::new(ChaCha20Rng::seed_from_u64(12345), "ChaCha20"));
+        // Two random bits in every byte
+        let mut rng = ChaCha20Rng::seed_from_u64(67890);
+        let narrow = (0..4096).map(|_| (rng.next_u32() % 4) as u8).collect();
+        entropy.add_source(PhysicalEntropy::new(narrow, "Narrow"));
+
+        let stats = entropy.stats();
+        assert!(stats[0].estimated && stats[0].entropy_per_byte > 4.0);
+        assert!(stats[1].estimated && stats[1].entropy_per_byte < 2.0);
+
+        // Only what a request drew is credited
+        let mut bytes = [0u8; 32];
+        entropy.fill_bytes(&mut bytes);
+        let stats = entropy.stats();
+        assert_eq!(stats[0].bytes_drawn, stats[1].bytes_drawn);
+        assert!(stats[0].credited_bits + stats[1].credited_bits >= 256.0);
+    }
+
+    #[test]
+    fn test_stuck_source_is_quarantined() {
+        let mut entropy = CombinedEntropy::new();
+        entropy.add_source(RngEntropy::new(ChaCha20Rng::seed_from_u64(12345), "ChaCha20"));
+        entropy.add_source(PhysicalEntropy::new(vec![0x42; 1024], "Stuck Sensor"));
+
+        // The stuck source fails its startup tests and is left out
+        let health = entropy.health();
+        assert_eq!(health.len(), 2);
+        assert_eq!(health[0].status, HealthStatus::Healthy);
+        assert_eq!(health[0].samples_tested, 1024);
+        assert!(matches!(
+            health[1].status,
+            HealthStatus::Quarantined(HealthFailure::RepetitionCount { value: 0x42, .. })
+        ));
+        assert!(entropy.is_healthy());
+
+        let mut bytes = [0u8; 64];
+        assert!(entropy.try_fill_bytes(&mut bytes).is_ok());
+    }
+
+    #[test]
+    fn test_no_healthy_source_is_an_error() {
+        let mut entropy = CombinedEntropy::new();
+        entropy.add_source(PhysicalEntropy::new(vec![0x42; 2048], "Stuck Sensor"));
+        assert!(!entropy.is_healthy());
+
+        let mut bytes = [0u8; 32];
+        let err = entropy.try_fill_bytes(&mut bytes).unwrap_err();
+        assert!(err.to_string().contains("No healthy entropy source"));
+
+        // A short startup run lets the stuck source back in, until the
+        // continuous tests catch it again
+        entropy.set_health_config(HealthConfig { startup_samples: 16, ..HealthConfig::default() });
+        assert!(entropy.is_healthy());
+        assert!(entropy.try_fill_bytes(&mut bytes).is_err());
+    }
+
+    #[test]
+    fn test_physical_source_is_not_replayed() {
+        let mut source = PhysicalEntropy::new((0..100).collect(), "Short Recording");
+        let mut bytes = [0u8; 60];
+        source.fill_bytes(&mut bytes).unwrap();
+        assert_eq!(bytes[59], 59);
+
+        // Forty bytes are left, so a second request of sixty fails
+        let err = source.fill_bytes(&mut bytes).unwrap_err();
+        assert!(err.to_string().contains("Short Recording exhausted"));
+        source.fill_bytes(&mut bytes[..40]).unwrap();
+        assert_eq!(bytes[39], 99);
+        assert!(source.fill_bytes(&mut bytes[..1]).is_err());
+    }
+
     #[test]
     fn test_ios_sensor_entropy() -> Result<()> {
         use std::time::{SystemTime, UNIX_EPOCH};
@@ -415,6 +892,7 @@ mod tests {
                 sample_rate: 100.0,
                 signal_to_noise: 10.0,
                 temporal_consistency: 0.9,
+                min_entropy_per_bit: 0.8,
             },
         };
 
@@ -0,0 +1,615 @@
+//! Recorded sensor data and its replay
+//!
+//! A [`Recording`] holds timestamped samples of one or more channels. It
+//! reads and writes CSV, one `seconds,value[,value...]` row per sample, and
+//! a compact binary layout: magic, version, channel count (u32 LE), then
+//! for every sample its timestamp in microseconds (u64 LE) followed by each
+//! channel as an f64 (LE). [`ReplaySensor`] plays a recording back as a
+//! [`Sensor`], and [`Recording::capture`] records a live one.
+
+use super::estimators;
+use super::sensor::{EntropyQuality, Sensor, SensorConfig};
+use crate::utils::Reader;
+use crate::{FskcError, Result};
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const RECORDING_MAGIC: [u8; 4] = *b"FSKS";
+const RECORDING_VERSION: u8 = 1;
+
+/// One timestamped sample of every channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedSample {
+    /// Time since the recording started, to the microsecond
+    pub timestamp: Duration,
+    /// Value of each channel
+    pub values: Vec<f64>,
+}
+
+/// Timestamped multi-channel sensor samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    channels: usize,
+    samples: Vec<RecordedSample>,
+}
+
+impl Recording {
+    /// Creates an empty recording with `channels` values per sample
+    pub fn new(channels: usize) -> Result<Self> {
+        if channels == 0 {
+            return Err(FskcError::SensorError("A recording needs at least one channel".into()));
+        }
+        Ok(Self { channels, samples: Vec::new() })
+    }
+
+    /// Appends a sample, truncating its timestamp to the microsecond
+    ///
+    /// Timestamps may not decrease, and every sample needs one value per
+    /// channel.
+    pub fn push(&mut self, timestamp: Duration, values: Vec<f64>) -> Result<()> {
+        self.append(timestamp, values).map_err(FskcError::SensorError)
+    }
+
+    /// Captures `samples` bytes from a started sensor as a one-channel
+    /// recording, one byte per sample at the sensor's sample rate
+    ///
+    /// Reads are paced against the wall clock and stamped with their
+    /// scheduled time, so replaying the recording at the same rate yields
+    /// the same bytes.
+    pub fn capture(sensor: &mut dyn Sensor, samples: usize) -> Result<Self> {
+        let interval = sample_interval(sensor.config().sample_rate)?;
+        let mut recording = Self::new(1)?;
+        let started = Instant::now();
+        let mut byte = [0u8; 1];
+        for k in 0..samples as u64 {
+            let timestamp = Duration::from_nanos(k * interval);
+            thread::sleep(timestamp.saturating_sub(started.elapsed()));
+            sensor.fill_entropy(&mut byte)?;
+            recording.push(timestamp, vec![byte[0] as f64])?;
+        }
+        Ok(recording)
+    }
+
+    /// Number of values in each sample
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// The samples in time order
+    pub fn samples(&self) -> &[RecordedSample] {
+        &self.samples
+    }
+
+    /// Returns the number of samples
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns true if the recording has no samples
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Parses CSV rows of `seconds,value[,value...]`
+    ///
+    /// Blank lines, `#` comments and a header as the first row are skipped.
+    pub fn from_csv(text: &str) -> Result<Self> {
+        let mut recording: Option<Self> = None;
+        let mut first_row = true;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let malformed = |message: String| FskcError::FormatError(format!("CSV line {}: {}", number + 1, message));
+
+            let row: std::result::Result<Vec<f64>, _> = line.split(',').map(|field| field.trim().parse::<f64>()).collect();
+            let row = match row {
+                Ok(row) => row,
+                Err(_) if first_row => {
+                    first_row = false;
+                    continue;
+                }
+                Err(e) => return Err(malformed(e.to_string())),
+            };
+            first_row = false;
+
+            if row.len() < 2 {
+                return Err(malformed("no channel values".into()));
+            }
+            if !(row[0].is_finite() && row[0] >= 0.0) {
+                return Err(malformed(format!("invalid timestamp {}", row[0])));
+            }
+            let recording = match &mut recording {
+                Some(recording) => recording,
+                None => recording.insert(Self::new(row.len() - 1)?),
+            };
+            let timestamp = Duration::from_micros((row[0] * 1e6).round() as u64);
+            recording.append(timestamp, row[1..].to_vec()).map_err(malformed)?;
+        }
+        recording.ok_or_else(|| FskcError::FormatError("CSV recording has no samples".into()))
+    }
+
+    /// Writes the recording as CSV with a header row
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("seconds");
+        for channel in 0..self.channels {
+            out.push_str(&format!(",channel{}", channel));
+        }
+        out.push('\n');
+        for sample in &self.samples {
+            let micros = sample.timestamp.as_micros();
+            out.push_str(&format!("{}.{:06}", micros / 1_000_000, micros % 1_000_000));
+            for value in &sample.values {
+                out.push_str(&format!(",{}", value));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Decodes the binary layout
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data, "sensor recording");
+        if reader.take(RECORDING_MAGIC.len())? != RECORDING_MAGIC {
+            return Err(FskcError::FormatError("Not a sensor recording".into()));
+        }
+        let version = reader.u8()?;
+        if version != RECORDING_VERSION {
+            return Err(FskcError::UnsupportedVersion(version));
+        }
+
+        let channels = reader.u32()? as usize;
+        let mut recording = Self::new(channels).map_err(|e| FskcError::FormatError(e.to_string()))?;
+        while reader.remaining() > 0 {
+            let timestamp = Duration::from_micros(reader.u64()?);
+            // Grows with the input rather than the claimed channel count
+            let mut values = Vec::new();
+            for _ in 0..channels {
+                values.push(f64::from_bits(reader.u64()?));
+            }
+            recording.append(timestamp, values).map_err(FskcError::FormatError)?;
+        }
+        Ok(recording)
+    }
+
+    /// Encodes the binary layout
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(9 + self.samples.len() * 8 * (self.channels + 1));
+        out.extend_from_slice(&RECORDING_MAGIC);
+        out.push(RECORDING_VERSION);
+        out.extend_from_slice(&(self.channels as u32).to_le_bytes());
+        for sample in &self.samples {
+            out.extend_from_slice(&(sample.timestamp.as_micros() as u64).to_le_bytes());
+            for value in &sample.values {
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    /// Reads a recording in either format, told apart by the binary magic
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let data = fs::read(path)?;
+        if data.starts_with(&RECORDING_MAGIC) {
+            return Self::from_bytes(&data);
+        }
+        let text = String::from_utf8(data)
+            .map_err(|_| FskcError::FormatError("Recording is neither binary nor CSV".into()))?;
+        Self::from_csv(&text)
+    }
+
+    /// Writes the recording in the binary layout
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    fn append(&mut self, timestamp: Duration, values: Vec<f64>) -> std::result::Result<(), String> {
+        if values.len() != self.channels {
+            return Err(format!("expected {} channels, found {}", self.channels, values.len()));
+        }
+        let timestamp = Duration::from_micros(timestamp.as_micros() as u64);
+        if self.samples.last().is_some_and(|last| last.timestamp > timestamp) {
+            return Err(format!("timestamp {:?} goes back in time", timestamp));
+        }
+        self.samples.push(RecordedSample { timestamp, values });
+        Ok(())
+    }
+}
+
+/// Sensor that plays back a [`Recording`]
+///
+/// Samples are taken at the configured sample rate along the recording's
+/// timeline, holding each recorded value until the next one, and the last
+/// for as long as the one before it. Every channel
+/// of a sample yields one byte: the low byte of the value counted in steps
+/// of the resolution. Playback is as fast as it is read unless a time scale
+/// paces it against the wall clock. Reading past the end is an error.
+pub struct ReplaySensor {
+    recording: Recording,
+    description: String,
+    config: SensorConfig,
+    resolution: f64,
+    time_scale: Option<f64>,
+    running: bool,
+    started: Instant,
+    /// Time of the next sample since the first recorded one, in nanoseconds
+    clock: u64,
+    /// Next channel of the sample at `clock`
+    channel: usize,
+    /// Index of the recorded sample held at `clock`
+    cursor: usize,
+    last_quality: EntropyQuality,
+}
+
+impl ReplaySensor {
+    pub fn new(recording: Recording, description: impl Into<String>) -> Self {
+        Self {
+            recording,
+            description: description.into(),
+            config: SensorConfig::default(),
+            resolution: 1.0,
+            time_scale: None,
+            running: false,
+            started: Instant::now(),
+            clock: 0,
+            channel: 0,
+            cursor: 0,
+            last_quality: EntropyQuality::default(),
+        }
+    }
+
+    /// Loads a recording from a CSV or binary file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let description = format!("Replay of {}", path.as_ref().display());
+        Ok(Self::new(Recording::load(path)?, description))
+    }
+
+    /// Sets the value step that one unit of an output byte stands for
+    pub fn with_resolution(mut self, resolution: f64) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Paces playback against the wall clock, `scale` times faster than recorded
+    pub fn with_time_scale(mut self, scale: f64) -> Self {
+        self.time_scale = Some(scale);
+        self
+    }
+
+    /// Returns to the start of the recording
+    pub fn rewind(&mut self) {
+        self.started = Instant::now();
+        self.clock = 0;
+        self.channel = 0;
+        self.cursor = 0;
+        self.update_quality();
+    }
+
+    fn origin(&self) -> u64 {
+        self.recording.samples.first().map_or(0, |s| s.timestamp.as_nanos() as u64)
+    }
+
+    /// Time since the first recorded sample at which playback runs out
+    fn end(&self) -> u64 {
+        let timestamps: Vec<u64> = self.recording.samples
+            .iter()
+            .rev()
+            .take(2)
+            .map(|s| s.timestamp.as_nanos() as u64 - self.origin())
+            .collect();
+        match timestamps[..] {
+            [last, previous] if last > previous => 2 * last - previous,
+            [last, ..] => last + sample_interval(self.config.sample_rate).unwrap_or(1),
+            [] => 0,
+        }
+    }
+
+    fn value_byte(&self, value: f64) -> u8 {
+        (value / self.resolution).round() as i64 as u8
+    }
+
+    /// Recomputes quality over the recorded samples of the current window
+    fn update_quality(&mut self) {
+        let window = self.config.window.as_nanos() as u64;
+        let end = self.origin() + self.clock.max(window);
+        let start = end.saturating_sub(window);
+        let samples: Vec<&RecordedSample> = self.recording.samples
+            .iter()
+            .filter(|s| (start..=end).contains(&(s.timestamp.as_nanos() as u64)))
+            .collect();
+        if samples.is_empty() {
+            return;
+        }
+        let bytes: Vec<u8> = samples
+            .iter()
+            .flat_map(|s| s.values.iter().map(|&v| self.value_byte(v)))
+            .collect();
+
+        // Shannon entropy of the output bytes, as a fraction of 8 bits
+        let mut counts = [0usize; 256];
+        for &byte in &bytes {
+            counts[byte as usize] += 1;
+        }
+        let shannon = counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / bytes.len() as f64;
+                -p * p.log2()
+            })
+            .sum::<f64>() / 8.0;
+
+        // Signal-to-noise ratio and temporal consistency across all channels
+        let mut signal = 0.0;
+        let mut noise = 0.0;
+        let mut diffs = Vec::new();
+        for pair in samples.windows(2) {
+            for (a, b) in pair[0].values.iter().zip(&pair[1].values) {
+                signal += a.abs();
+                noise += (b - a).abs();
+                diffs.push((b - a).abs());
+            }
+        }
+        let snr = if noise == 0.0 { 0.0 } else { signal / noise };
+        let mut consistency = 0.0;
+        if !diffs.is_empty() {
+            let mean_diff = diffs.iter().sum::<f64>() / diffs.len() as f64;
+            let var_diff = diffs.iter()
+                .map(|&d| (d - mean_diff).powi(2))
+                .sum::<f64>() / diffs.len() as f64;
+            consistency = 1.0 / (1.0 + var_diff);
+        }
+
+        self.last_quality = EntropyQuality {
+            shannon_entropy: shannon,
+            sample_rate: self.config.sample_rate,
+            signal_to_noise: snr,
+            temporal_consistency: consistency,
+            min_entropy_per_bit: estimators::estimate(&bytes).min_entropy_per_bit(),
+        };
+    }
+}
+
+impl Sensor for ReplaySensor {
+    fn check_hardware(&self) -> bool {
+        !self.recording.is_empty()
+    }
+
+    /// Starts or resumes playback; use [`ReplaySensor::rewind`] to restart
+    fn start(&mut self, config: &SensorConfig) -> Result<()> {
+        if !self.check_hardware() {
+            return Err(FskcError::EntropyError("Recording is empty".into()));
+        }
+        sample_interval(config.sample_rate)?;
+        if let Some(scale) = self.time_scale {
+            if !(scale.is_finite() && scale > 0.0) {
+                return Err(FskcError::SensorError(format!("Invalid time scale {}", scale)));
+            }
+        }
+        self.config = config.clone();
+        if !self.running {
+            // Resumes where playback stopped
+            let elapsed = Duration::from_nanos(self.clock).div_f64(self.time_scale.unwrap_or(1.0));
+            self.started = Instant::now().checked_sub(elapsed).unwrap_or_else(Instant::now);
+            self.running = true;
+        }
+        self.update_quality();
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.running = false;
+        Ok(())
+    }
+
+    fn quality(&self) -> Result<EntropyQuality> {
+        Ok(self.last_quality)
+    }
+
+    fn fill_entropy(&mut self, buffer: &mut [u8]) -> Result<()> {
+        if !self.running {
+            return Err(FskcError::EntropyError("Sensor not running".into()));
+        }
+        let interval = sample_interval(self.config.sample_rate)?;
+        let channels = self.recording.channels;
+
+        // The last recorded sample is held as long as the one before it
+        let samples_needed = ((self.channel + buffer.len()) as u64).div_ceil(channels as u64);
+        let last = samples_needed.saturating_sub(1) * interval + self.clock;
+        if !buffer.is_empty() && last >= self.end() {
+            return Err(FskcError::SensorError("Recording exhausted".into()));
+        }
+
+        let origin = self.origin();
+        for byte in buffer.iter_mut() {
+            let samples = &self.recording.samples;
+            while self.cursor + 1 < samples.len()
+                && samples[self.cursor + 1].timestamp.as_nanos() as u64 <= origin + self.clock
+            {
+                self.cursor += 1;
+            }
+            *byte = self.value_byte(samples[self.cursor].values[self.channel]);
+
+            self.channel += 1;
+            if self.channel == channels {
+                self.channel = 0;
+                self.clock += interval;
+            }
+        }
+
+        if let Some(scale) = self.time_scale {
+            let due = Duration::from_nanos(last).div_f64(scale);
+            thread::sleep(due.saturating_sub(self.started.elapsed()));
+        }
+        self.update_quality();
+        Ok(())
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn config(&self) -> &SensorConfig {
+        &self.config
+    }
+}
+
+/// Time between samples at `sample_rate`, in nanoseconds
+fn sample_interval(sample_rate: f64) -> Result<u64> {
+    let interval = (1e9 / sample_rate).round();
+    if !(sample_rate.is_finite() && interval >= 1.0 && interval < u64::MAX as f64) {
+        return Err(FskcError::SensorError(format!("Invalid sample rate {}", sample_rate)));
+    }
+    Ok(interval as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::{CombinedEntropy, HealthStatus};
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    /// One channel of `len` random bytes at 100 Hz
+    fn random_recording(seed: u64, len: usize) -> Recording {
+        let mut bytes = vec![0u8; len];
+        ChaCha20Rng::seed_from_u64(seed).fill_bytes(&mut bytes);
+        let mut recording = Recording::new(1).unwrap();
+        for (i, &byte) in bytes.iter().enumerate() {
+            recording.push(Duration::from_millis(10 * i as u64), vec![byte as f64]).unwrap();
+        }
+        recording
+    }
+
+    fn started(sensor: ReplaySensor, sample_rate: f64) -> ReplaySensor {
+        let mut sensor = sensor;
+        sensor.start(&SensorConfig { sample_rate, ..SensorConfig::default() }).unwrap();
+        sensor
+    }
+
+    #[test]
+    fn test_csv_and_binary_roundtrip() {
+        let csv = "# field recording\nseconds,x,y\n0.0,1.5,-2\n0.010000,1.25,-2.5\n\n0.02,1e-3,0\n";
+        let recording = Recording::from_csv(csv).unwrap();
+        assert_eq!(recording.channels(), 2);
+        assert_eq!(recording.len(), 3);
+        assert_eq!(recording.samples()[1].timestamp, Duration::from_millis(10));
+        assert_eq!(recording.samples()[2].values, vec![0.001, 0.0]);
+
+        assert_eq!(Recording::from_csv(&recording.to_csv()).unwrap(), recording);
+        assert_eq!(Recording::from_bytes(&recording.to_bytes()).unwrap(), recording);
+
+        let path = std::env::temp_dir().join(format!("fskc-recording-{}.bin", std::process::id()));
+        recording.save(&path).unwrap();
+        assert_eq!(Recording::load(&path).unwrap(), recording);
+        fs::write(&path, recording.to_csv()).unwrap();
+        assert_eq!(ReplaySensor::from_file(&path).unwrap().recording, recording);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_malformed_recordings() {
+        assert!(Recording::from_csv("").is_err());
+        assert!(Recording::from_csv("0,1\n0.1,1,2\n").unwrap_err().to_string().contains("line 2"));
+        assert!(Recording::from_csv("0.2,1\n0.1,1\n").unwrap_err().to_string().contains("back in time"));
+        assert!(Recording::from_csv("0,1\n0.1,x\n").is_err());
+        assert!(Recording::from_csv("-1,1\n").is_err());
+
+        let bytes = random_recording(1, 4).to_bytes();
+        assert!(Recording::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Recording::from_bytes(b"FSKT\x01").is_err());
+        let mut future = bytes.clone();
+        future[4] = 2;
+        assert!(matches!(Recording::from_bytes(&future), Err(FskcError::UnsupportedVersion(2))));
+    }
+
+    #[test]
+    fn test_rejects_selector_snapshot() {
+        let selector = crate::RovingSelector::new(4, 10, &crate::Key256::insecure_from_u64(12345)).unwrap();
+        let err = Recording::from_bytes(&selector.snapshot()).unwrap_err();
+        assert!(err.to_string().contains("Not a sensor recording"));
+    }
+
+    #[test]
+    fn test_replay_follows_sample_rate() {
+        // Values 0..10 recorded at 10 Hz, in steps of a thousandth
+        let mut recording = Recording::new(1).unwrap();
+        for i in 0..10 {
+            recording.push(Duration::from_millis(100 * i), vec![i as f64 / 1000.0]).unwrap();
+        }
+        let replay = |sample_rate: f64, len: usize| {
+            let mut sensor = started(ReplaySensor::new(recording.clone(), "Replay").with_resolution(0.001), sample_rate);
+            let mut bytes = vec![0u8; len];
+            sensor.fill_entropy(&mut bytes).map(|()| bytes)
+        };
+
+        assert_eq!(replay(10.0, 10).unwrap(), (0..10).collect::<Vec<u8>>());
+        assert_eq!(replay(20.0, 6).unwrap(), vec![0, 0, 1, 1, 2, 2]);
+        assert_eq!(replay(5.0, 5).unwrap(), vec![0, 2, 4, 6, 8]);
+        assert!(replay(10.0, 11).is_err());
+        assert!(replay(20.0, 20).is_ok());
+
+        // Every channel of a sample gives a byte
+        let stereo = Recording::from_csv("0,1,2\n0.5,3,4\n").unwrap();
+        let mut sensor = started(ReplaySensor::new(stereo, "Stereo"), 2.0);
+        let mut bytes = [0u8; 3];
+        sensor.fill_entropy(&mut bytes).unwrap();
+        assert_eq!(bytes, [1, 2, 3]);
+        sensor.fill_entropy(&mut bytes[..1]).unwrap();
+        assert_eq!(bytes[0], 4);
+        assert!(sensor.fill_entropy(&mut bytes[..1]).is_err());
+    }
+
+    #[test]
+    fn test_time_scale_paces_playback() {
+        // 200 ms of recording played ten times faster
+        let mut sensor = started(ReplaySensor::new(random_recording(1, 20), "Replay").with_time_scale(10.0), 100.0);
+        let begin = Instant::now();
+        let mut bytes = [0u8; 20];
+        sensor.fill_entropy(&mut bytes).unwrap();
+        assert!(begin.elapsed() >= Duration::from_millis(19));
+    }
+
+    #[test]
+    fn test_capture_then_replay() {
+        let mut live = started(ReplaySensor::new(random_recording(2, 256), "Live"), 10_000.0);
+        let captured = Recording::capture(&mut live, 200).unwrap();
+        assert_eq!(captured.len(), 200);
+
+        let mut expected = [0u8; 200];
+        started(ReplaySensor::new(random_recording(2, 256), "Live"), 10_000.0)
+            .fill_entropy(&mut expected)
+            .unwrap();
+        let mut replayed = [0u8; 200];
+        started(ReplaySensor::new(captured, "Replay"), 10_000.0)
+            .fill_entropy(&mut replayed)
+            .unwrap();
+        assert_eq!(replayed, expected);
+    }
+
+    #[test]
+    fn test_replay_feeds_combined_entropy() {
+        let pool = || {
+            let mut entropy = CombinedEntropy::new();
+            entropy.add_sensor(ReplaySensor::new(random_recording(3, 8192), "Replay")).unwrap();
+            entropy
+        };
+
+        let mut entropy = pool();
+        let stats = entropy.stats();
+        assert_eq!(stats[0].status, HealthStatus::Healthy);
+        assert!(stats[0].entropy_per_byte > 4.0);
+        assert!(entropy.sensor_quality()[0].1.min_entropy_per_bit > 0.5);
+
+        // The same recording gives the same output
+        let mut first = [0u8; 32];
+        let mut second = [0u8; 32];
+        entropy.fill_bytes(&mut first);
+        pool().fill_bytes(&mut second);
+        assert_eq!(first, second);
+    }
+}
@@ -1,5 +1,6 @@
 use std::time::Duration;
 use crate::Result;
+use super::estimators;
 
 /// Represents the quality of entropy from a sensor
 #[derive(Debug, Clone, Copy, Default)]
@@ -12,6 +13,8 @@ pub struct EntropyQuality {
     pub signal_to_noise: f64,
     /// Temporal consistency score (0.0 to 1.0)
     pub temporal_consistency: f64,
+    /// Conservative min-entropy estimate in bits per output bit (0.0 to 1.0)
+    pub min_entropy_per_bit: f64,
 }
 
 /// Configuration for sensor sampling
@@ -60,6 +63,14 @@ pub trait Sensor: Send + Sync {
     
     /// Get current configuration
     fn config(&self) -> &SensorConfig;
+
+    /// Declared min-entropy of one output byte, in bits
+    ///
+    /// Sensors returning `None` are credited at a rate estimated from
+    /// their startup samples.
+    fn entropy_per_byte(&self) -> Option<f64> {
+        None
+    }
 }
 
 /// Accelerometer sensor implementation
@@ -95,6 +106,7 @@ impl Accelerometer {
                 sample_rate: 0.0,
                 signal_to_noise: 0.0,
                 temporal_consistency: 0.0,
+                min_entropy_per_bit: 0.0,
             },
         }
     }
@@ -131,6 +143,18 @@ impl Accelerometer {
         entropy
     }
 
+    /// Byte emitted for one sample
+    fn sample_byte(sample: &[f64; 3]) -> u8 {
+        let mixed = sample[0].abs() + sample[1].abs() + sample[2].abs();
+        (mixed * 255.0) as u8
+    }
+
+    /// Estimate min-entropy of the emitted bytes
+    fn calculate_min_entropy(&self) -> f64 {
+        let bytes: Vec<u8> = self.samples.iter().map(Self::sample_byte).collect();
+        estimators::estimate(&bytes).min_entropy_per_bit()
+    }
+
     /// Update quality metrics based on recent samples
     fn update_quality(&mut self) {
         if self.samples.is_empty() {
@@ -170,6 +194,7 @@ impl Accelerometer {
             sample_rate: self.config.sample_rate,
             signal_to_noise: snr,
             temporal_consistency: consistency,
+            min_entropy_per_bit: self.calculate_min_entropy(),
         };
     }
 }
@@ -209,8 +234,7 @@ impl Sensor for Accelerometer {
         // In practice, would use proper whitening and extraction methods
         for (i, byte) in buffer.iter_mut().enumerate() {
             if let Some(sample) = self.samples.get(i % self.samples.len()) {
-                let mixed = sample[0].abs() + sample[1].abs() + sample[2].abs();
-                *byte = (mixed * 255.0) as u8;
+                *byte = Self::sample_byte(sample);
             }
         }
 
@@ -261,6 +285,7 @@ impl Barometer {
                 sample_rate: 0.0,
                 signal_to_noise: 0.0,
                 temporal_consistency: 0.0,
+                min_entropy_per_bit: 0.0,
             },
         }
     }
@@ -294,6 +319,17 @@ impl Barometer {
         entropy
     }
 
+    /// Byte emitted for one sample
+    fn sample_byte(sample: f64) -> u8 {
+        (sample.abs() * 255.0) as u8
+    }
+
+    /// Estimate min-entropy of the emitted bytes
+    fn calculate_min_entropy(&self) -> f64 {
+        let bytes: Vec<u8> = self.samples.iter().map(|&s| Self::sample_byte(s)).collect();
+        estimators::estimate(&bytes).min_entropy_per_bit()
+    }
+
     fn update_quality(&mut self) {
         if self.samples.is_empty() {
             return;
@@ -328,6 +364,7 @@ impl Barometer {
             sample_rate: self.config.sample_rate,
             signal_to_noise: snr,
             temporal_consistency: consistency,
+            min_entropy_per_bit: self.calculate_min_entropy(),
         };
     }
 }
@@ -366,7 +403,7 @@ impl Sensor for Barometer {
         // Simple entropy extraction for demonstration
         for (i, byte) in buffer.iter_mut().enumerate() {
             if let Some(sample) = self.samples.get(i % self.samples.len()) {
-                *byte = (sample.abs() * 255.0) as u8;
+                *byte = Self::sample_byte(*sample);
             }
         }
 
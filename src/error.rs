@@ -1,6 +1,5 @@
 use thiserror::Error;
 use rand::Error as RngError;
-use getrandom::Error as GetRandomError;
 use std::time::SystemTimeError;
 
 /// Custom error types for FSKC operations
@@ -42,6 +41,24 @@ pub enum FskcError {
     #[error("Sensor error: {0}")]
     SensorError(String),
 
+    #[error("Malformed data: {0}")]
+    FormatError(String),
+
+    #[error("Unsupported format version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Invalid layer spec at column {column} near '{token}': {message}")]
+    SpecError {
+        column: usize,
+        token: String,
+        message: String,
+    },
+
+    #[error("Integrity check failed for subtree {path:?}")]
+    IntegrityError {
+        path: Vec<usize>,
+    },
+
     #[error("{0}")]
     Custom(String),
 }
@@ -53,8 +70,8 @@ impl From<RngError> for FskcError {
 }
 
 impl From<FskcError> for RngError {
-    fn from(_: FskcError) -> Self {
-        RngError::from(GetRandomError::UNSUPPORTED)
+    fn from(error: FskcError) -> Self {
+        RngError::new(error)
     }
 }
 
@@ -0,0 +1,113 @@
+//! Binary encoding of fractal trees
+//!
+//! Layout: magic, version, root depth (u32 LE), root hash, then every
+//! node in pre-order as flags, optional salt, value length (u32 LE),
+//! value and child count (u32 LE). Seeds are never written; the decoder
+//! derives them from the key material it is given.
+
+use super::{FractalNode, MerkleHash, SALT_LEN};
+use crate::utils::Reader;
+use crate::{error::FskcError, Key256, Result};
+use std::sync::Arc;
+
+const TREE_MAGIC: [u8; 4] = *b"FSKT";
+const TREE_VERSION: u8 = 1;
+
+/// Node flag: the node carries an update salt
+const FLAG_SALT: u8 = 0x01;
+
+/// Deepest nesting accepted from untrusted input
+const MAX_NESTING: usize = 64;
+
+pub(super) fn encode(node: &FractalNode) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(node.payload_size() + 64);
+    out.extend_from_slice(&TREE_MAGIC);
+    out.push(TREE_VERSION);
+    out.extend_from_slice(&encode_u32(node.depth)?);
+    out.extend_from_slice(&node.commitment);
+    encode_node(&mut out, node)?;
+    Ok(out)
+}
+
+fn encode_node(out: &mut Vec<u8>, node: &FractalNode) -> Result<()> {
+    match &node.salt {
+        Some(salt) => {
+            out.push(FLAG_SALT);
+            out.extend_from_slice(salt);
+        }
+        None => out.push(0),
+    }
+    out.extend_from_slice(&encode_u32(node.value.len())?);
+    out.extend_from_slice(&node.value);
+    out.extend_from_slice(&encode_u32(node.children.len())?);
+    for child in &node.children {
+        encode_node(out, child)?;
+    }
+    Ok(())
+}
+
+pub(super) fn decode(data: &[u8], initial_seed: &Key256) -> Result<Arc<FractalNode>> {
+    let mut reader = Reader::new(data, "fractal tree");
+    if reader.take(TREE_MAGIC.len())? != TREE_MAGIC {
+        return Err(FskcError::FormatError("Not an encoded fractal tree".into()));
+    }
+    let version = reader.u8()?;
+    if version != TREE_VERSION {
+        return Err(FskcError::UnsupportedVersion(version));
+    }
+
+    let depth = reader.u32()? as usize;
+    let root_hash: MerkleHash = reader.array()?;
+
+    let node = decode_node(&mut reader, initial_seed.clone(), depth, 0)?;
+    if !reader.rest().is_empty() {
+        return Err(FskcError::FormatError("Trailing bytes after fractal tree".into()));
+    }
+    if node.commitment != root_hash {
+        return Err(FskcError::IntegrityError { path: Vec::new() });
+    }
+    Ok(Arc::new(node))
+}
+
+fn decode_node(reader: &mut Reader, seed: Key256, depth: usize, nesting: usize) -> Result<FractalNode> {
+    if depth == 0 {
+        return Err(FskcError::FormatError("Fractal node at depth 0".into()));
+    }
+    if nesting > MAX_NESTING {
+        return Err(FskcError::FormatError("Fractal tree nested too deeply".into()));
+    }
+
+    let flags = reader.u8()?;
+    if flags & !FLAG_SALT != 0 {
+        return Err(FskcError::FormatError(format!("Unknown node flags {:#04x}", flags)));
+    }
+    let salt = if flags & FLAG_SALT != 0 {
+        Some(reader.array::<SALT_LEN>()?)
+    } else {
+        None
+    };
+
+    let value_len = reader.u32()? as usize;
+    let value = reader.take(value_len)?.to_vec();
+
+    let child_count = reader.u32()? as usize;
+    if child_count > 0 && depth == 1 {
+        return Err(FskcError::FormatError("Fractal leaf depth with children".into()));
+    }
+    // Grows with the input rather than the claimed count
+    let mut children = Vec::new();
+    for i in 0..child_count {
+        let child_seed = seed.derive_child(i as u64);
+        children.push(Arc::new(decode_node(reader, child_seed, depth - 1, nesting + 1)?));
+    }
+
+    let mut node = FractalNode::with_children(value, seed, depth, children);
+    node.salt = salt;
+    Ok(node)
+}
+
+fn encode_u32(value: usize) -> Result<[u8; 4]> {
+    u32::try_from(value)
+        .map(u32::to_le_bytes)
+        .map_err(|_| FskcError::FormatError(format!("Value {} out of range", value)))
+}
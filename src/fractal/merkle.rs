@@ -0,0 +1,158 @@
+use crate::utils::Reader;
+use crate::{error::FskcError, Result};
+use sha2::{Digest, Sha256};
+
+/// SHA-256 commitment to a node and everything below it
+pub type MerkleHash = [u8; 32];
+
+/// Domain separation between leaf and inner node commitments
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+
+const PROOF_MAGIC: [u8; 4] = *b"FSKP";
+const PROOF_VERSION: u8 = 1;
+
+/// Most steps accepted from untrusted input, matching the deepest tree
+/// the tree codec accepts
+const MAX_STEPS: usize = 64;
+
+/// Encoded length of a hash
+const HASH_LEN: usize = 32;
+
+/// Hashes the encrypted value stored at a node
+pub(crate) fn value_digest(value: &[u8]) -> MerkleHash {
+    Sha256::digest(value).into()
+}
+
+/// Commits to a node's value digest and the commitments of its children
+pub(crate) fn commit(value_digest: &MerkleHash, children: &[MerkleHash]) -> MerkleHash {
+    let mut hasher = Sha256::new();
+    hasher.update([if children.is_empty() { LEAF_TAG } else { NODE_TAG }]);
+    hasher.update(value_digest);
+    for child in children {
+        hasher.update(child);
+    }
+    hasher.finalize().into()
+}
+
+/// Proof that one leaf ciphertext chunk belongs to a tree with a given root
+///
+/// Each step carries the value digest of an ancestor and the commitments
+/// of the ancestor's other children, so the leaf can be checked without
+/// the rest of the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof {
+    leaf_index: usize,
+    /// Steps from the root down to the leaf's parent
+    steps: Vec<ProofStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProofStep {
+    pub(crate) value_digest: MerkleHash,
+    pub(crate) position: usize,
+    pub(crate) siblings: Vec<MerkleHash>,
+}
+
+impl InclusionProof {
+    pub(crate) fn new(leaf_index: usize, steps: Vec<ProofStep>) -> Self {
+        Self { leaf_index, steps }
+    }
+
+    /// Returns the index of the proven leaf, counting leaves left to right
+    pub fn leaf_index(&self) -> usize {
+        self.leaf_index
+    }
+
+    /// Returns the child positions leading from the root to the leaf
+    pub fn path(&self) -> Vec<usize> {
+        self.steps.iter().map(|step| step.position).collect()
+    }
+
+    /// Recomputes the root commitment implied by `leaf_ciphertext`
+    pub fn root(&self, leaf_ciphertext: &[u8]) -> MerkleHash {
+        let mut hash = commit(&value_digest(leaf_ciphertext), &[]);
+        for step in self.steps.iter().rev() {
+            let mut children = step.siblings.clone();
+            children.insert(step.position.min(children.len()), hash);
+            hash = commit(&step.value_digest, &children);
+        }
+        hash
+    }
+
+    /// Serializes the proof for a receiver without the tree
+    ///
+    /// Layout: magic, version, leaf index (u64 LE), step count (u32 LE),
+    /// then for every step its value digest, position (u32 LE), sibling
+    /// count (u32 LE) and sibling commitments.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let hashes: usize = self.steps.iter().map(|step| step.siblings.len() + 1).sum();
+        let mut out = Vec::with_capacity(17 + 8 * self.steps.len() + HASH_LEN * hashes);
+        out.extend_from_slice(&PROOF_MAGIC);
+        out.push(PROOF_VERSION);
+        out.extend_from_slice(&(self.leaf_index as u64).to_le_bytes());
+        out.extend_from_slice(&(self.steps.len() as u32).to_le_bytes());
+        for step in &self.steps {
+            out.extend_from_slice(&step.value_digest);
+            out.extend_from_slice(&(step.position as u32).to_le_bytes());
+            out.extend_from_slice(&(step.siblings.len() as u32).to_le_bytes());
+            for sibling in &step.siblings {
+                out.extend_from_slice(sibling);
+            }
+        }
+        out
+    }
+
+    /// Parses a proof written by [`InclusionProof::to_bytes`]
+    ///
+    /// Only the shape of the proof is checked here; whether it proves
+    /// anything is up to [`InclusionProof::verify`].
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data, "inclusion proof");
+        if reader.take(PROOF_MAGIC.len())? != PROOF_MAGIC {
+            return Err(FskcError::FormatError("Not an inclusion proof".into()));
+        }
+        let version = reader.u8()?;
+        if version != PROOF_VERSION {
+            return Err(FskcError::UnsupportedVersion(version));
+        }
+
+        let leaf_index = usize::try_from(reader.u64()?)
+            .map_err(|_| FskcError::FormatError("Leaf index out of range".into()))?;
+        let step_count = reader.u32()? as usize;
+        if step_count > MAX_STEPS {
+            return Err(FskcError::FormatError(format!("Proof of {} steps is too deep", step_count)));
+        }
+
+        let mut steps = Vec::with_capacity(step_count);
+        for _ in 0..step_count {
+            let value_digest = reader.array()?;
+            let position = reader.u32()? as usize;
+            let sibling_count = reader.u32()? as usize;
+            if sibling_count > reader.remaining() / HASH_LEN {
+                return Err(FskcError::FormatError("Truncated inclusion proof".into()));
+            }
+            if position > sibling_count {
+                return Err(FskcError::FormatError(format!("Proof position {} out of range", position)));
+            }
+            let siblings = (0..sibling_count)
+                .map(|_| reader.array())
+                .collect::<Result<Vec<MerkleHash>>>()?;
+            steps.push(ProofStep { value_digest, position, siblings });
+        }
+
+        if !reader.rest().is_empty() {
+            return Err(FskcError::FormatError("Trailing bytes after inclusion proof".into()));
+        }
+        Ok(Self { leaf_index, steps })
+    }
+
+    /// Checks `leaf_ciphertext` against the root of a trusted tree
+    pub fn verify(&self, root: &MerkleHash, leaf_ciphertext: &[u8]) -> Result<()> {
+        if &self.root(leaf_ciphertext) == root {
+            Ok(())
+        } else {
+            Err(FskcError::IntegrityError { path: self.path() })
+        }
+    }
+}
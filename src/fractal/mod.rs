@@ -0,0 +1,933 @@
+use crate::{crypto, error::FskcError, Key256, Permutation, Result};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+mod codec;
+mod merkle;
+
+pub use merkle::{InclusionProof, MerkleHash};
+use merkle::ProofStep;
+
+/// Nodes covering at least this many bytes process their children in parallel
+const PARALLEL_MIN_LEN: usize = 16 * 1024;
+
+/// Length of the random salt that refreshes the key of an updated node
+const SALT_LEN: usize = 16;
+
+/// Represents a node in the fractal encryption structure
+///
+/// Every node commits to its encrypted value and to its children's
+/// commitments, so the root hash authenticates the whole tree.
+#[derive(Debug, Clone)]
+pub struct FractalNode {
+    /// The encrypted data block at this node
+    value: Vec<u8>,
+    /// The key derived from the node's position in the tree
+    seed: Key256,
+    /// Salt mixed into the seed once the node has been updated
+    salt: Option<[u8; SALT_LEN]>,
+    /// Child nodes in the fractal structure
+    children: Vec<Arc<FractalNode>>,
+    /// Depth level in the fractal structure
+    depth: usize,
+    /// Merkle commitment over the value and the children's commitments
+    commitment: MerkleHash,
+    /// Length of the plaintext covered by this node
+    len: usize,
+}
+
+impl FractalNode {
+    /// Creates a new FractalNode with the given parameters
+    pub fn new(value: Vec<u8>, seed: Key256, depth: usize) -> Self {
+        Self::with_children(value, seed, depth, Vec::new())
+    }
+
+    fn with_children(
+        value: Vec<u8>,
+        seed: Key256,
+        depth: usize,
+        children: Vec<Arc<FractalNode>>,
+    ) -> Self {
+        let commitment = Self::compute_commitment(&value, &children);
+        let len = if children.is_empty() {
+            value.len()
+        } else {
+            children.iter().map(|child| child.len).sum()
+        };
+        Self {
+            value,
+            seed,
+            salt: None,
+            children,
+            depth,
+            commitment,
+            len,
+        }
+    }
+
+    fn compute_commitment(value: &[u8], children: &[Arc<FractalNode>]) -> MerkleHash {
+        let children: Vec<MerkleHash> = children.iter().map(|child| child.commitment).collect();
+        merkle::commit(&merkle::value_digest(value), &children)
+    }
+
+    /// Generates a fractal structure from the input data
+    ///
+    /// Each child is seeded with a key derived from its parent's key and
+    /// its position. Children of large nodes are generated in parallel; the
+    /// result is identical to [`FractalNode::generate_sequential`].
+    pub fn generate(
+        data: Vec<u8>,
+        initial_seed: &Key256,
+        max_depth: usize,
+        chunk_size: usize,
+    ) -> Result<Arc<Self>> {
+        Self::validate(&data, max_depth, chunk_size)?;
+        Self::build(data, initial_seed, max_depth, chunk_size, true, false)
+    }
+
+    /// Generates a fractal structure on the calling thread only
+    pub fn generate_sequential(
+        data: Vec<u8>,
+        initial_seed: &Key256,
+        max_depth: usize,
+        chunk_size: usize,
+    ) -> Result<Arc<Self>> {
+        Self::validate(&data, max_depth, chunk_size)?;
+        Self::build(data, initial_seed, max_depth, chunk_size, false, false)
+    }
+
+    /// Generates a fractal structure that stores payload only at its leaves
+    ///
+    /// Inner nodes of a full tree hold an encrypted copy of everything
+    /// below them, which `decrypt` never reads. The compact tree decrypts
+    /// to the same data and has the same root hash as the tree rebuilt by
+    /// [`FractalNode::from_ciphertext`].
+    pub fn generate_compact(
+        data: Vec<u8>,
+        initial_seed: &Key256,
+        max_depth: usize,
+        chunk_size: usize,
+    ) -> Result<Arc<Self>> {
+        Self::validate(&data, max_depth, chunk_size)?;
+        Self::build(data, initial_seed, max_depth, chunk_size, true, true)
+    }
+
+    fn validate(data: &[u8], max_depth: usize, chunk_size: usize) -> Result<()> {
+        if max_depth == 0 {
+            return Err(FskcError::InvalidDepth(0));
+        }
+
+        if data.is_empty() {
+            return Err(FskcError::InvalidDataSize(0));
+        }
+
+        if chunk_size == 0 {
+            return Err(FskcError::InvalidDataSize(chunk_size));
+        }
+
+        Ok(())
+    }
+
+    fn build(
+        data: Vec<u8>,
+        initial_seed: &Key256,
+        max_depth: usize,
+        chunk_size: usize,
+        parallel: bool,
+        compact: bool,
+    ) -> Result<Arc<Self>> {
+        let has_children = Self::has_children(data.len(), max_depth, chunk_size);
+
+        // XOR the data with RNG output, unless a compact tree keeps it in the children
+        let encrypted_data = if compact && has_children {
+            Vec::new()
+        } else {
+            let mut rng = initial_seed.rng();
+            crypto::xor_with_rng(&data, &mut rng)?
+        };
+
+        // Only create child nodes if we haven't reached max depth
+        let mut children = Vec::new();
+        if has_children {
+            // Derive a new seed for each child
+            let child = |(i, chunk): (usize, &[u8])| {
+                let child_seed = initial_seed.derive_child(i as u64);
+                Self::build(chunk.to_vec(), &child_seed, max_depth - 1, chunk_size, parallel, compact)
+            };
+
+            children = if parallel && data.len() >= PARALLEL_MIN_LEN {
+                data.par_chunks(chunk_size).enumerate().map(child).collect::<Result<_>>()?
+            } else {
+                data.chunks(chunk_size).enumerate().map(child).collect::<Result<_>>()?
+            };
+        }
+
+        Ok(Arc::new(Self::with_children(encrypted_data, initial_seed.clone(), max_depth, children)))
+    }
+
+    /// Rebuilds a fractal structure from the output of [`FractalNode::ciphertext`]
+    ///
+    /// The tree shape and per-node seeds are recomputed from the parameters,
+    /// and the ciphertext is distributed over the leaves. The result is a
+    /// compact tree, as built by [`FractalNode::generate_compact`].
+    pub fn from_ciphertext(
+        ciphertext: Vec<u8>,
+        initial_seed: &Key256,
+        max_depth: usize,
+        chunk_size: usize,
+    ) -> Result<Arc<Self>> {
+        Self::validate(&ciphertext, max_depth, chunk_size)?;
+        Ok(Self::rebuild(ciphertext, initial_seed, max_depth, chunk_size))
+    }
+
+    fn rebuild(
+        ciphertext: Vec<u8>,
+        initial_seed: &Key256,
+        max_depth: usize,
+        chunk_size: usize,
+    ) -> Arc<Self> {
+        if !Self::has_children(ciphertext.len(), max_depth, chunk_size) {
+            return Arc::new(Self::new(ciphertext, initial_seed.clone(), max_depth));
+        }
+
+        let child = |(i, chunk): (usize, &[u8])| {
+            let child_seed = initial_seed.derive_child(i as u64);
+            Self::rebuild(chunk.to_vec(), &child_seed, max_depth - 1, chunk_size)
+        };
+
+        let children = if ciphertext.len() >= PARALLEL_MIN_LEN {
+            ciphertext.par_chunks(chunk_size).enumerate().map(child).collect()
+        } else {
+            ciphertext.chunks(chunk_size).enumerate().map(child).collect()
+        };
+
+        Arc::new(Self::with_children(Vec::new(), initial_seed.clone(), max_depth, children))
+    }
+
+    /// Returns the encrypted node values that `decrypt` reads, in order
+    ///
+    /// These are the values of the leaf nodes, so the output has the same
+    /// length as the original data.
+    pub fn ciphertext(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.value.len());
+        self.collect_ciphertext(&mut out);
+        out
+    }
+
+    fn collect_ciphertext(&self, out: &mut Vec<u8>) {
+        if self.children.is_empty() {
+            out.extend_from_slice(&self.value);
+        } else {
+            for child in &self.children {
+                child.collect_ciphertext(out);
+            }
+        }
+    }
+
+    /// Serializes the tree structure and ciphertext
+    ///
+    /// Seeds are not written; [`FractalNode::decode`] derives them again
+    /// from the key material the tree was generated with.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        codec::encode(self)
+    }
+
+    /// Parses a tree written by [`FractalNode::encode`]
+    ///
+    /// Malformed input is rejected with a `FormatError` or
+    /// `UnsupportedVersion`, and a tree whose commitments do not match the
+    /// encoded root hash with an `IntegrityError`. A wrong `initial_seed`
+    /// is not detected and decrypts to garbage.
+    pub fn decode(data: &[u8], initial_seed: &Key256) -> Result<Arc<Self>> {
+        codec::decode(data, initial_seed)
+    }
+
+    /// Returns a copy of this tree without the payload of inner nodes
+    ///
+    /// Leaves are shared with this tree.
+    pub fn compact(&self) -> Arc<Self> {
+        if self.children.is_empty() {
+            return Arc::new(self.clone());
+        }
+
+        let children = self.children
+            .iter()
+            .map(|child| if child.children.is_empty() { Arc::clone(child) } else { child.compact() })
+            .collect();
+        let mut node = Self::with_children(Vec::new(), self.seed.clone(), self.depth, children);
+        node.salt = self.salt;
+        Arc::new(node)
+    }
+
+    /// Returns true if no inner node of the tree stores payload
+    pub fn is_compact(&self) -> bool {
+        self.children.is_empty()
+            || (self.value.is_empty() && self.children.iter().all(|child| child.is_compact()))
+    }
+
+    /// Returns the number of payload bytes stored across all nodes
+    pub fn payload_size(&self) -> usize {
+        self.value.len() + self.children.iter().map(|child| child.payload_size()).sum::<usize>()
+    }
+
+    /// Returns the payload bytes stored beyond the ciphertext itself
+    ///
+    /// These are the redundant copies held by inner nodes, so the overhead
+    /// of a compact tree is zero.
+    pub fn overhead_size(&self) -> usize {
+        self.payload_size() - self.len
+    }
+
+    /// Returns the leaf values in the order given by `order`
+    ///
+    /// `order` must have one position per leaf. Leaves differ in length
+    /// where the data is not a multiple of the chunk size, which
+    /// [`FractalNode::from_ordered_ciphertext`] recomputes from the shape.
+    pub fn ordered_ciphertext(&self, order: &Permutation) -> Result<Vec<u8>> {
+        let mut leaves = Vec::with_capacity(self.leaf_count());
+        self.collect_leaves(&mut leaves);
+        Ok(order.permute(&leaves)?.concat())
+    }
+
+    fn collect_leaves<'a>(&'a self, out: &mut Vec<&'a [u8]>) {
+        if self.children.is_empty() {
+            out.push(&self.value);
+        } else {
+            for child in &self.children {
+                child.collect_leaves(out);
+            }
+        }
+    }
+
+    /// Rebuilds a fractal structure from the output of
+    /// [`FractalNode::ordered_ciphertext`]
+    pub fn from_ordered_ciphertext(
+        ciphertext: Vec<u8>,
+        order: &Permutation,
+        initial_seed: &Key256,
+        max_depth: usize,
+        chunk_size: usize,
+    ) -> Result<Arc<Self>> {
+        Self::validate(&ciphertext, max_depth, chunk_size)?;
+
+        let mut lengths = Vec::new();
+        Self::leaf_lengths(ciphertext.len(), max_depth, chunk_size, &mut lengths);
+        let mut rest = ciphertext.as_slice();
+        let leaves: Vec<&[u8]> = order
+            .permute(&lengths)?
+            .into_iter()
+            .map(|len| {
+                let (leaf, tail) = rest.split_at(len);
+                rest = tail;
+                leaf
+            })
+            .collect();
+
+        let ciphertext = order.unpermute(&leaves)?.concat();
+        Ok(Self::rebuild(ciphertext, initial_seed, max_depth, chunk_size))
+    }
+
+    /// Returns the number of leaves in a tree over `len` bytes
+    pub fn leaf_count_for(len: usize, max_depth: usize, chunk_size: usize) -> usize {
+        let mut lengths = Vec::new();
+        Self::leaf_lengths(len, max_depth, chunk_size, &mut lengths);
+        lengths.len()
+    }
+
+    fn leaf_lengths(len: usize, max_depth: usize, chunk_size: usize, out: &mut Vec<usize>) {
+        if !Self::has_children(len, max_depth, chunk_size) {
+            out.push(len);
+            return;
+        }
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = remaining.min(chunk_size);
+            Self::leaf_lengths(chunk, max_depth - 1, chunk_size, out);
+            remaining -= chunk;
+        }
+    }
+
+    /// Whether a node over `len` bytes is split into children
+    fn has_children(len: usize, max_depth: usize, chunk_size: usize) -> bool {
+        max_depth > 1 && len > chunk_size
+    }
+
+    /// Decrypts the fractal structure and returns the original data
+    ///
+    /// Children of large nodes are decrypted in parallel; the result is
+    /// identical to [`FractalNode::decrypt_sequential`].
+    pub fn decrypt(&self) -> Result<Vec<u8>> {
+        self.decrypt_with(true)
+    }
+
+    /// Decrypts the fractal structure on the calling thread only
+    pub fn decrypt_sequential(&self) -> Result<Vec<u8>> {
+        self.decrypt_with(false)
+    }
+
+    fn decrypt_with(&self, parallel: bool) -> Result<Vec<u8>> {
+        // If this node has children, decrypt them and combine the results
+        if !self.children.is_empty() {
+            let parts: Vec<Vec<u8>> = if parallel && self.children.len() > 1 {
+                self.children
+                    .par_iter()
+                    .map(|child| child.decrypt_with(parallel))
+                    .collect::<Result<_>>()?
+            } else {
+                self.children
+                    .iter()
+                    .map(|child| child.decrypt_with(parallel))
+                    .collect::<Result<_>>()?
+            };
+
+            return Ok(parts.concat());
+        }
+
+        // Initialize RNG with the node's key and decrypt this node's value
+        let mut rng = self.key().rng();
+        crypto::xor_with_rng(&self.value, &mut rng)
+    }
+
+    /// Decrypts `len` bytes starting at `offset` of the original data
+    ///
+    /// Only the subtrees overlapping the range are visited, and leaves seek
+    /// their keystream to the first byte needed. The output equals the same
+    /// slice of [`FractalNode::decrypt`].
+    pub fn decrypt_range(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
+        let end = offset.checked_add(len).filter(|&end| end <= self.len).ok_or_else(|| {
+            FskcError::Custom(format!(
+                "Range of {} bytes at offset {} exceeds {} bytes of data", len, offset, self.len
+            ))
+        })?;
+
+        let mut out = Vec::with_capacity(len);
+        self.decrypt_range_into(offset, end, &mut out)?;
+        Ok(out)
+    }
+
+    fn decrypt_range_into(&self, start: usize, end: usize, out: &mut Vec<u8>) -> Result<()> {
+        if start >= end {
+            return Ok(());
+        }
+
+        if self.children.is_empty() {
+            // ChaCha20Rng emits the keystream in 4-byte words
+            let mut rng = self.key().rng();
+            let skip = start % 4;
+            rng.set_word_pos(((start - skip) / 4) as u128);
+            let mut keystream = vec![0u8; end - start + skip];
+            rng.fill_bytes(&mut keystream);
+            out.extend(self.value[start..end].iter().zip(&keystream[skip..]).map(|(c, k)| c ^ k));
+            return Ok(());
+        }
+
+        let mut child_start = 0;
+        for child in &self.children {
+            let child_end = child_start + child.len;
+            if child_end > start && child_start < end {
+                child.decrypt_range_into(
+                    start.saturating_sub(child_start),
+                    end.min(child_end) - child_start,
+                    out,
+                )?;
+            }
+            if child_end >= end {
+                break;
+            }
+            child_start = child_end;
+        }
+        Ok(())
+    }
+
+    /// Returns a new tree with `new_bytes` written at `offset`
+    ///
+    /// Only the leaves overlapping the write and their ancestors are
+    /// re-encrypted, each under a fresh random salt so no keystream is
+    /// reused. All other subtrees are shared with this tree, which stays
+    /// valid as a snapshot of the previous version.
+    ///
+    /// The salts are not part of [`FractalNode::ciphertext`], so an updated
+    /// tree cannot be rebuilt with [`FractalNode::from_ciphertext`].
+    pub fn update(&self, offset: usize, new_bytes: &[u8]) -> Result<Arc<Self>> {
+        let end = offset.checked_add(new_bytes.len()).filter(|&end| end <= self.len).ok_or_else(|| {
+            FskcError::Custom(format!(
+                "Write of {} bytes at offset {} exceeds {} bytes of data", new_bytes.len(), offset, self.len
+            ))
+        })?;
+        if offset == end {
+            return Ok(Arc::new(self.clone()));
+        }
+
+        self.update_at(offset, new_bytes).map(Arc::new)
+    }
+
+    fn update_at(&self, offset: usize, new_bytes: &[u8]) -> Result<Self> {
+        let end = offset + new_bytes.len();
+
+        let mut children = Vec::with_capacity(self.children.len());
+        let mut child_start = 0;
+        for child in &self.children {
+            let child_end = child_start + child.len;
+            if child_end > offset && child_start < end {
+                let start = offset.max(child_start);
+                let bytes = &new_bytes[start - offset..end.min(child_end) - offset];
+                children.push(Arc::new(child.update_at(start - child_start, bytes)?));
+            } else {
+                children.push(Arc::clone(child));
+            }
+            child_start = child_end;
+        }
+
+        // Inner nodes of compact trees carry no value to refresh
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.try_fill_bytes(&mut salt)?;
+        let value = if self.value.is_empty() {
+            Vec::new()
+        } else {
+            let mut plaintext = crypto::xor_with_rng(&self.value, &mut self.key().rng())?;
+            plaintext[offset..end].copy_from_slice(new_bytes);
+            crypto::xor_with_rng(&plaintext, &mut Self::salted_key(&self.seed, &salt).rng())?
+        };
+
+        let mut node = Self::with_children(value, self.seed.clone(), self.depth, children);
+        node.salt = Some(salt);
+        Ok(node)
+    }
+
+    /// Returns the key of this node's keystream
+    fn key(&self) -> Key256 {
+        match &self.salt {
+            Some(salt) => Self::salted_key(&self.seed, salt),
+            None => self.seed.clone(),
+        }
+    }
+
+    fn salted_key(seed: &Key256, salt: &[u8; SALT_LEN]) -> Key256 {
+        let mut label = b"fskc-update".to_vec();
+        label.extend_from_slice(salt);
+        seed.derive(&label)
+    }
+
+    /// Returns the length of the data covered by this node
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the node covers no data
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the Merkle commitment to this node and its subtree
+    pub fn root_hash(&self) -> MerkleHash {
+        self.commitment
+    }
+
+    /// Recomputes every commitment in the tree and compares it with the
+    /// stored one
+    ///
+    /// On failure the error carries the child positions leading from this
+    /// node to the deepest subtree whose commitment does not match.
+    pub fn verify(&self) -> Result<()> {
+        let mut path = Vec::new();
+        self.verify_at(&mut path)
+    }
+
+    fn verify_at(&self, path: &mut Vec<usize>) -> Result<()> {
+        for (i, child) in self.children.iter().enumerate() {
+            path.push(i);
+            child.verify_at(path)?;
+            path.pop();
+        }
+
+        if Self::compute_commitment(&self.value, &self.children) == self.commitment {
+            Ok(())
+        } else {
+            Err(FskcError::IntegrityError { path: path.clone() })
+        }
+    }
+
+    /// Returns the number of leaves, whose values form the ciphertext
+    pub fn leaf_count(&self) -> usize {
+        if self.children.is_empty() {
+            1
+        } else {
+            self.children.iter().map(|child| child.leaf_count()).sum()
+        }
+    }
+
+    /// Builds a proof that leaf `leaf_index` belongs to this tree
+    ///
+    /// Leaves are counted left to right, matching the order of chunks in
+    /// [`FractalNode::ciphertext`].
+    pub fn prove(&self, leaf_index: usize) -> Result<InclusionProof> {
+        let mut steps = Vec::new();
+        let mut node = self;
+        let mut remaining = leaf_index;
+
+        while !node.children.is_empty() {
+            let mut position = None;
+            for (i, child) in node.children.iter().enumerate() {
+                let count = child.leaf_count();
+                if remaining < count {
+                    position = Some(i);
+                    break;
+                }
+                remaining -= count;
+            }
+            let position = position.ok_or_else(|| {
+                FskcError::Custom(format!("Leaf index {} out of range", leaf_index))
+            })?;
+
+            steps.push(ProofStep {
+                value_digest: merkle::value_digest(&node.value),
+                position,
+                siblings: node.children.iter()
+                    .enumerate()
+                    .filter(|&(i, _)| i != position)
+                    .map(|(_, child)| child.commitment)
+                    .collect(),
+            });
+            node = &node.children[position];
+        }
+
+        if remaining != 0 {
+            return Err(FskcError::Custom(format!("Leaf index {} out of range", leaf_index)));
+        }
+        Ok(InclusionProof::new(leaf_index, steps))
+    }
+
+    /// Returns the depth of this node in the fractal structure
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the number of child nodes
+    pub fn child_count(&self) -> usize {
+        self.children.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fractal_encryption_decryption() {
+        let data = b"Hello, Fractal World!".to_vec();
+        let seed = Key256::insecure_from_u64(12345);
+        let depth = 3;
+        let chunk_size = 4;
+
+        // Generate fractal structure
+        let node = FractalNode::generate(data.clone(), &seed, depth, chunk_size)
+            .expect("Failed to generate fractal");
+
+        // Decrypt the data
+        let decrypted = node.decrypt().expect("Failed to decrypt");
+
+        // Verify the decryption
+        assert_eq!(data, decrypted, "Decrypted data doesn't match original");
+    }
+
+    #[test]
+    fn test_ciphertext_roundtrip() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data = b"Hello, Fractal World! Serialized leaves".to_vec();
+
+        for (depth, chunk_size) in [(1, 4), (2, 4), (3, 8), (3, 64)] {
+            let node = FractalNode::generate(data.clone(), &seed, depth, chunk_size).unwrap();
+            let ciphertext = node.ciphertext();
+            assert_eq!(ciphertext.len(), data.len());
+            assert_ne!(ciphertext, data);
+
+            let rebuilt = FractalNode::from_ciphertext(ciphertext, &seed, depth, chunk_size).unwrap();
+            assert_eq!(rebuilt.child_count(), node.child_count());
+            assert_eq!(rebuilt.decrypt().unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data: Vec<u8> = (0..3 * PARALLEL_MIN_LEN).map(|i| (i * 7) as u8).collect();
+
+        for (depth, chunk_size) in [(2, 1024), (3, 4096), (4, 64)] {
+            let parallel = FractalNode::generate(data.clone(), &seed, depth, chunk_size).unwrap();
+            let sequential = FractalNode::generate_sequential(data.clone(), &seed, depth, chunk_size).unwrap();
+            assert_eq!(parallel.ciphertext(), sequential.ciphertext());
+
+            let rebuilt = FractalNode::from_ciphertext(parallel.ciphertext(), &seed, depth, chunk_size).unwrap();
+            assert_eq!(rebuilt.decrypt().unwrap(), data);
+            assert_eq!(rebuilt.decrypt_sequential().unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_children_use_derived_keys() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data = vec![0u8; 64];
+
+        // Identical chunks must not share a keystream
+        let node = FractalNode::generate(data, &seed, 2, 16).unwrap();
+        let ciphertext = node.ciphertext();
+        let chunks: Vec<_> = ciphertext.chunks(16).collect();
+        assert!(chunks.iter().enumerate().all(|(i, a)| chunks[i + 1..].iter().all(|b| a != b)));
+
+        let other = FractalNode::generate(vec![0u8; 64], &seed.derive(b"other"), 2, 16).unwrap();
+        assert_ne!(other.ciphertext(), ciphertext);
+    }
+
+    #[test]
+    fn test_decrypt_range_matches_full_decrypt() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data: Vec<u8> = (0..200u32).map(|i| (i * 31) as u8).collect();
+
+        for (depth, chunk_size) in [(1, 16), (2, 16), (3, 7)] {
+            let node = FractalNode::generate(data.clone(), &seed, depth, chunk_size).unwrap();
+            assert_eq!(node.len(), data.len());
+            for (offset, len) in [(0, 200), (0, 0), (5, 1), (13, 40), (195, 5), (200, 0)] {
+                assert_eq!(node.decrypt_range(offset, len).unwrap(), &data[offset..offset + len]);
+            }
+
+            let rebuilt = FractalNode::from_ciphertext(node.ciphertext(), &seed, depth, chunk_size).unwrap();
+            assert_eq!(rebuilt.decrypt_range(33, 67).unwrap(), &data[33..100]);
+        }
+
+        let node = FractalNode::generate(data, &seed, 2, 16).unwrap();
+        assert!(node.decrypt_range(190, 11).is_err());
+        assert!(node.decrypt_range(usize::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_update_shares_untouched_subtrees() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data: Vec<u8> = (0..64u8).collect();
+        let node = FractalNode::generate(data.clone(), &seed, 2, 16).unwrap();
+
+        let updated = node.update(14, b"abcd").unwrap();
+        let mut expected = data.clone();
+        expected[14..18].copy_from_slice(b"abcd");
+        assert_eq!(updated.decrypt().unwrap(), expected);
+        assert_eq!(updated.decrypt_range(10, 10).unwrap(), &expected[10..20]);
+        assert!(updated.verify().is_ok());
+        assert_ne!(updated.root_hash(), node.root_hash());
+
+        // The old root is an unchanged snapshot sharing the untouched chunks
+        assert_eq!(node.decrypt().unwrap(), data);
+        assert!(!Arc::ptr_eq(&node.children[0], &updated.children[0]));
+        assert!(!Arc::ptr_eq(&node.children[1], &updated.children[1]));
+        assert!(Arc::ptr_eq(&node.children[2], &updated.children[2]));
+        assert!(Arc::ptr_eq(&node.children[3], &updated.children[3]));
+
+        // Writing the same bytes again still draws a fresh keystream
+        let rewritten = node.update(0, &data[..16]).unwrap();
+        assert_eq!(rewritten.decrypt().unwrap(), data);
+        assert_ne!(rewritten.ciphertext()[..16], node.ciphertext()[..16]);
+
+        assert!(node.update(60, b"too long").is_err());
+
+        let rebuilt = FractalNode::from_ciphertext(node.ciphertext(), &seed, 2, 16).unwrap();
+        assert_eq!(rebuilt.update(30, b"xy").unwrap().decrypt_range(30, 2).unwrap(), b"xy");
+    }
+
+    #[test]
+    fn test_compact_storage() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data: Vec<u8> = (0..100u8).collect();
+
+        let full = FractalNode::generate(data.clone(), &seed, 3, 10).unwrap();
+        let compact = FractalNode::generate_compact(data.clone(), &seed, 3, 10).unwrap();
+        assert!(!full.is_compact());
+        assert!(compact.is_compact());
+        assert_eq!(full.payload_size(), 200);
+        assert_eq!(full.overhead_size(), 100);
+        assert_eq!(compact.payload_size(), 100);
+        assert_eq!(compact.overhead_size(), 0);
+
+        assert_eq!(compact.decrypt().unwrap(), data);
+        assert_eq!(compact.ciphertext(), full.ciphertext());
+        assert!(compact.verify().is_ok());
+
+        let rebuilt = FractalNode::from_ciphertext(full.ciphertext(), &seed, 3, 10).unwrap();
+        assert_eq!(rebuilt.root_hash(), compact.root_hash());
+        assert_eq!(full.compact().root_hash(), compact.root_hash());
+
+        // Updates keep a compact tree compact
+        let updated = compact.update(5, b"xyz").unwrap();
+        assert!(updated.is_compact());
+        assert_eq!(updated.decrypt_range(5, 3).unwrap(), b"xyz");
+        assert_eq!(updated.compact().decrypt().unwrap(), updated.decrypt().unwrap());
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data: Vec<u8> = (0..100u8).collect();
+        let full = FractalNode::generate(data.clone(), &seed, 3, 16).unwrap();
+        let updated = full.compact().update(40, b"updated").unwrap();
+
+        for tree in [full, updated] {
+            let encoded = tree.encode().unwrap();
+            let decoded = FractalNode::decode(&encoded, &seed).unwrap();
+            assert_eq!(decoded.root_hash(), tree.root_hash());
+            assert_eq!(decoded.decrypt().unwrap(), tree.decrypt().unwrap());
+            assert_eq!(decoded.encode().unwrap(), encoded);
+
+            // Seeds stay out of the encoding
+            let other = FractalNode::decode(&encoded, &seed.derive(b"other")).unwrap();
+            assert_ne!(other.decrypt().unwrap(), tree.decrypt().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_input() {
+        let seed = Key256::insecure_from_u64(12345);
+        let tree = FractalNode::generate(b"Encoded fractal tree".to_vec(), &seed, 2, 8).unwrap();
+        let encoded = tree.encode().unwrap();
+
+        for len in 0..encoded.len() {
+            assert!(matches!(FractalNode::decode(&encoded[..len], &seed), Err(FskcError::FormatError(_))));
+        }
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(matches!(FractalNode::decode(&trailing, &seed), Err(FskcError::FormatError(_))));
+
+        let mut version = encoded.clone();
+        version[4] = 9;
+        assert!(matches!(FractalNode::decode(&version, &seed), Err(FskcError::UnsupportedVersion(9))));
+
+        let mut flipped = encoded.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        let result = FractalNode::decode(&flipped, &seed);
+        assert!(matches!(result, Err(FskcError::IntegrityError { .. }) | Err(FskcError::FormatError(_))));
+
+        // Payload bytes are covered by the root hash
+        let mut tampered = encoded;
+        let last_leaf = tampered.len() - 5;
+        tampered[last_leaf] ^= 1;
+        assert!(matches!(FractalNode::decode(&tampered, &seed), Err(FskcError::IntegrityError { .. })));
+
+        // Arbitrary bytes never panic
+        let mut rng = seed.rng();
+        for _ in 0..200 {
+            let mut junk = vec![0u8; (rng.next_u32() % 64) as usize];
+            rng.fill_bytes(&mut junk);
+            junk.splice(0..0, *b"FSKT\x01");
+            assert!(FractalNode::decode(&junk, &seed).is_err());
+        }
+    }
+
+    #[test]
+    fn test_ordered_ciphertext() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data: Vec<u8> = (0..50u8).collect();
+        let node = FractalNode::generate(data.clone(), &seed, 2, 8).unwrap();
+
+        assert_eq!(FractalNode::leaf_count_for(data.len(), 2, 8), node.leaf_count());
+        let order = Permutation::new(&seed.derive(b"order"), node.leaf_count()).unwrap();
+        let ordered = node.ordered_ciphertext(&order).unwrap();
+        assert_eq!(ordered.len(), data.len());
+        assert_ne!(ordered, node.ciphertext());
+
+        let rebuilt = FractalNode::from_ordered_ciphertext(ordered, &order, &seed, 2, 8).unwrap();
+        assert_eq!(rebuilt.decrypt().unwrap(), data);
+
+        let wrong = Permutation::new(&seed, 3).unwrap();
+        assert!(node.ordered_ciphertext(&wrong).is_err());
+    }
+
+    #[test]
+    fn test_verify_reports_tampered_subtree() {
+        let seed = Key256::insecure_from_u64(12345);
+        let node = FractalNode::generate(b"Authenticated fractal leaves".to_vec(), &seed, 2, 8).unwrap();
+        assert!(node.verify().is_ok());
+
+        // A modified leaf no longer matches its own commitment
+        let mut tampered = (*node).clone();
+        let mut leaf = (*tampered.children[1]).clone();
+        leaf.value[0] ^= 1;
+        tampered.children[1] = Arc::new(leaf);
+        assert!(matches!(tampered.verify(), Err(FskcError::IntegrityError { path }) if path == [1]));
+
+        // A consistent replacement subtree no longer matches the parent
+        tampered.children[1] = Arc::new(FractalNode::new(vec![0; 8], seed, 1));
+        assert!(matches!(tampered.verify(), Err(FskcError::IntegrityError { path }) if path.is_empty()));
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data = b"Each chunk can be checked on its own".to_vec();
+        let node = FractalNode::generate(data, &seed, 3, 8).unwrap();
+        let root = node.root_hash();
+        let ciphertext = node.ciphertext();
+
+        assert_eq!(node.leaf_count(), 5);
+        for (i, chunk) in ciphertext.chunks(8).enumerate() {
+            let proof = node.prove(i).unwrap();
+            assert_eq!(proof.path(), [i]);
+            assert!(proof.verify(&root, chunk).is_ok());
+
+            let mut forged = chunk.to_vec();
+            forged[0] ^= 1;
+            assert!(proof.verify(&root, &forged).is_err());
+        }
+        assert!(node.prove(5).is_err());
+
+        // A single-node tree proves its value against itself
+        let leaf = FractalNode::generate(vec![1, 2, 3], &seed, 1, 8).unwrap();
+        assert!(leaf.prove(0).unwrap().verify(&leaf.root_hash(), &leaf.ciphertext()).is_ok());
+    }
+
+    #[test]
+    fn test_inclusion_proof_encoding() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data = b"Proofs travel without the tree they came from".to_vec();
+        let node = FractalNode::generate(data, &seed, 3, 4).unwrap();
+        let root = node.root_hash();
+        let ciphertext = node.ciphertext();
+
+        // The receiver holds only the root, one chunk and the encoded proof
+        let bytes = node.prove(7).unwrap().to_bytes();
+        let proof = InclusionProof::from_bytes(&bytes).unwrap();
+        assert_eq!(proof, node.prove(7).unwrap());
+        assert_eq!(proof.leaf_index(), 7);
+        assert!(proof.verify(&root, &ciphertext[28..32]).is_ok());
+        assert!(proof.verify(&root, &ciphertext[24..28]).is_err());
+
+        for len in 0..bytes.len() {
+            assert!(InclusionProof::from_bytes(&bytes[..len]).is_err());
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(InclusionProof::from_bytes(&trailing).is_err());
+
+        // Step and sibling counts are checked before anything is allocated
+        let mut deep = bytes[..13].to_vec();
+        deep.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(InclusionProof::from_bytes(&deep), Err(FskcError::FormatError(_))));
+        let mut wide = bytes[..17 + 32 + 4].to_vec();
+        wide.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(InclusionProof::from_bytes(&wide), Err(FskcError::FormatError(_))));
+    }
+
+    #[test]
+    fn test_invalid_depth() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data = vec![1, 2, 3, 4];
+        let result = FractalNode::generate(data, &seed, 0, 2);
+        assert!(matches!(result, Err(FskcError::InvalidDepth(0))));
+    }
+
+    #[test]
+    fn test_empty_data() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data = vec![];
+        let result = FractalNode::generate(data, &seed, 3, 2);
+        assert!(matches!(result, Err(FskcError::InvalidDataSize(0))));
+    }
+}
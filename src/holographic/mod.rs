@@ -1,12 +1,12 @@
 pub mod compute;
 
-use crate::{Result, FskcError};
+use crate::{Result, FskcError, SecretBytes};
 use rand::{RngCore, CryptoRng};
 
 /// Represents a time step in the homomorphic sequence
 #[derive(Debug, Clone)]
 struct TimeStep {
-    key: Vec<u8>,
+    key: SecretBytes,
     operation: fn(u8, u8) -> u8,
     inverse: fn(u8, u8) -> u8,
 }
@@ -19,7 +19,7 @@ pub struct HolographicKeyPackage {
     // Current position in the sequence
     current_step: usize,
     // Root key for initialization
-    root_key: Vec<u8>,
+    root_key: SecretBytes,
 }
 
 /// Derived key package for enclave operations
@@ -30,11 +30,11 @@ pub struct DerivedKeyPackage {
     // Current position in the sequence
     current_step: usize,
     // Key derived from root key
-    derived_key: Vec<u8>,
+    derived_key: SecretBytes,
 }
 
 impl TimeStep {
-    fn new(key: Vec<u8>) -> Self {
+    fn new(key: SecretBytes) -> Self {
         Self {
             key,
             operation: |a, b| a ^ b,  // XOR for forward operation
@@ -51,8 +51,9 @@ impl TimeStep {
 }
 
 impl HolographicKeyPackage {
-    /// Creates a new root key package
+    /// Creates a new root key package, taking ownership of the key bytes
     pub fn new(root_key: Vec<u8>) -> Self {
+        let root_key = SecretBytes::new(root_key);
         Self {
             time_steps: vec![TimeStep::new(root_key.clone())],
             current_step: 0,
@@ -78,7 +79,7 @@ impl HolographicKeyPackage {
 
     /// Adds a new time step to the sequence
     pub fn add_time_step<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Result<()> {
-        let mut key = vec![0u8; self.root_key.len()];
+        let mut key = SecretBytes::zeroed(self.root_key.len());
         rng.fill_bytes(&mut key);
         self.time_steps.push(TimeStep::new(key));
         Ok(())
@@ -154,7 +155,7 @@ impl HolographicKeyPackage {
 impl DerivedKeyPackage {
     /// Adds a new time step to the sequence
     pub fn add_time_step<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Result<()> {
-        let mut key = vec![0u8; self.derived_key.len()];
+        let mut key = SecretBytes::zeroed(self.derived_key.len());
         rng.fill_bytes(&mut key);
         // XOR with derived key to maintain separation from root
         for (k, &d) in key.iter_mut().zip(self.derived_key.iter()) {
@@ -238,6 +239,9 @@ mod tests {
         let derived_pkg2 = root_pkg.derive_enclave_key()?;
         assert_eq!(derived_pkg.derived_key, derived_pkg2.derived_key);
 
+        // Key material never reaches debug output
+        assert!(!format!("{:?}", root_pkg).contains("66, 66"));
+
         Ok(())
     }
 
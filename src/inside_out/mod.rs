@@ -1,8 +1,8 @@
 use crate::{
     HolographicKeyPackage, HomomorphicCompute, Operation,
-    Result,
+    Key256, Result,
 };
-use rand::{RngCore, SeedableRng};
+use rand::RngCore;
 use rand_chacha::ChaCha20Rng;
 
 /// Represents a pair of homomorphic containers that manage their own relationship
@@ -139,17 +139,18 @@ pub struct EncryptedBlock {
 
 /// Represents the system's internal state with multiple compute pairs
 pub struct SystemState {
+    seed: Key256,
     pairs: Vec<ComputePair>,
     active_pair: usize,
     block_history: Vec<EncryptedBlock>,
 }
 
 impl SystemState {
-    pub fn new(seed: u64) -> Result<Self> {
-        let rng = ChaCha20Rng::seed_from_u64(seed);
-        let initial_pair = ComputePair::new(rng)?;
+    pub fn new(seed: &Key256) -> Result<Self> {
+        let initial_pair = ComputePair::new(seed.derive_child(0).rng())?;
         
         Ok(Self {
+            seed: seed.clone(),
             pairs: vec![initial_pair],
             active_pair: 0,
             block_history: Vec::new(),
@@ -157,7 +158,7 @@ impl SystemState {
     }
 
     fn add_pair(&mut self) -> Result<()> {
-        let rng = ChaCha20Rng::seed_from_u64(self.pairs.len() as u64);
+        let rng = self.seed.derive_child(self.pairs.len() as u64).rng();
         let pair = ComputePair::new(rng)?;
         self.pairs.push(pair);
         Ok(())
@@ -1,45 +1,79 @@
-use super::layer::{Layer, FractalLayer, SymmetricLayer};
+use std::sync::Arc;
+use super::layer::{Layer, FractalLayer, SymmetricLayer, TranspositionLayer};
 
 /// Represents a sequence of encryption layers
-pub type LayerSequence = Vec<Layer>;
+pub type LayerSequence = Vec<Arc<dyn Layer>>;
 
 /// Configuration for the layered encryption system
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct LayerConfig {
     pub(crate) sequence: LayerSequence,
     pub(crate) fractal_depth: usize,
     pub(crate) chunk_size: usize,
     pub(crate) use_zippering: bool,
+    pub(crate) parallel_chunk_size: Option<usize>,
 }
 
 impl Default for LayerConfig {
     fn default() -> Self {
         Self {
             sequence: vec![
-                Layer::Fractal(FractalLayer::new()),
-                Layer::Symmetric(SymmetricLayer::Aes),
+                Arc::new(FractalLayer::with_params(3, 64)),
+                Arc::new(SymmetricLayer::Aes),
             ],
             fractal_depth: 3,
             chunk_size: 64,
             use_zippering: false,
+            parallel_chunk_size: None,
         }
     }
 }
 
+impl PartialEq for LayerConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.fractal_depth == other.fractal_depth
+            && self.chunk_size == other.chunk_size
+            && self.use_zippering == other.use_zippering
+            && self.parallel_chunk_size == other.parallel_chunk_size
+            && self.sequence.len() == other.sequence.len()
+            && self.sequence.iter().zip(&other.sequence).all(|(a, b)| {
+                a.id() == b.id() && a.params() == b.params()
+            })
+    }
+}
+
 impl LayerConfig {
     /// Creates a new builder for LayerConfig
     pub fn builder() -> Builder {
         Builder::default()
     }
+
+    /// Returns the chunk size of chunk-parallel symmetric layers, if enabled
+    pub fn parallel_chunk_size(&self) -> Option<usize> {
+        self.parallel_chunk_size
+    }
+
+    /// Returns the layer sequence
+    pub fn layers(&self) -> &[Arc<dyn Layer>] {
+        &self.sequence
+    }
+}
+
+/// Layer queued in the builder; fractal layers pick up the final depth
+/// and chunk size when the config is built
+enum PendingLayer {
+    Fractal,
+    Custom(Arc<dyn Layer>),
 }
 
 /// Builder for configuring LayerConfig
 #[derive(Default)]
 pub struct Builder {
-    sequence: LayerSequence,
+    sequence: Vec<PendingLayer>,
     fractal_depth: Option<usize>,
     chunk_size: Option<usize>,
     use_zippering: bool,
+    parallel_chunk_size: Option<usize>,
 }
 
 impl Builder {
@@ -50,19 +84,43 @@ impl Builder {
 
     /// Adds a fractal layer to the sequence
     pub fn add_fractal(mut self) -> Self {
-        self.sequence.push(Layer::Fractal(FractalLayer::new()));
+        self.sequence.push(PendingLayer::Fractal);
         self
     }
 
     /// Adds an AES layer to the sequence
-    pub fn add_aes(mut self) -> Self {
-        self.sequence.push(Layer::Symmetric(SymmetricLayer::Aes));
-        self
+    pub fn add_aes(self) -> Self {
+        self.add_layer(SymmetricLayer::Aes)
+    }
+
+    /// Adds an unauthenticated ChaCha20 layer to the sequence
+    pub fn add_chacha(self) -> Self {
+        self.add_layer(SymmetricLayer::ChaCha)
+    }
+
+    /// Adds a ChaCha20-Poly1305 layer to the sequence
+    pub fn add_chacha_poly(self) -> Self {
+        self.add_layer(SymmetricLayer::ChaChaPoly)
+    }
+
+    /// Adds an XChaCha20-Poly1305 layer to the sequence
+    pub fn add_xchacha_poly(self) -> Self {
+        self.add_layer(SymmetricLayer::XChaChaPoly)
     }
 
-    /// Adds a ChaCha20 layer to the sequence
-    pub fn add_chacha(mut self) -> Self {
-        self.sequence.push(Layer::Symmetric(SymmetricLayer::ChaCha));
+    /// Adds a keyed byte transposition layer to the sequence
+    pub fn add_transposition(self) -> Self {
+        self.add_layer(TranspositionLayer)
+    }
+
+    /// Adds any layer to the sequence
+    pub fn add_layer<L: Layer + 'static>(self, layer: L) -> Self {
+        self.add_shared_layer(Arc::new(layer))
+    }
+
+    /// Adds a shared layer to the sequence
+    pub fn add_shared_layer(mut self, layer: Arc<dyn Layer>) -> Self {
+        self.sequence.push(PendingLayer::Custom(layer));
         self
     }
 
@@ -84,17 +142,46 @@ impl Builder {
         self
     }
 
+    /// Splits data into chunks of `size` bytes that symmetric layers
+    /// process in parallel; zero disables chunking
+    ///
+    /// Unauthenticated layers produce the same bytes as without chunking.
+    /// Authenticated layers deliberately use a different format: a single
+    /// GCM or Poly1305 tag covers the whole message and cannot be computed
+    /// across threads, so each chunk is sealed on its own with one tag per
+    /// chunk. The envelope header flags the chunk size, so readers open
+    /// either format without configuration, and ciphertexts written
+    /// without chunking are unaffected.
+    pub fn parallel_chunks(mut self, size: usize) -> Self {
+        self.parallel_chunk_size = Some(size).filter(|&size| size > 0);
+        self
+    }
+
     /// Builds the LayerConfig
     pub fn build(self) -> LayerConfig {
+        let fractal_depth = self.fractal_depth.unwrap_or(3);
+        let chunk_size = self.chunk_size.unwrap_or(64);
+
+        let sequence = if self.sequence.is_empty() {
+            LayerConfig::default().sequence
+        } else {
+            self.sequence
+                .into_iter()
+                .map(|pending| match pending {
+                    PendingLayer::Fractal => {
+                        Arc::new(FractalLayer::with_params(fractal_depth, chunk_size)) as Arc<dyn Layer>
+                    }
+                    PendingLayer::Custom(layer) => layer,
+                })
+                .collect()
+        };
+
         LayerConfig {
-            sequence: if self.sequence.is_empty() {
-                LayerConfig::default().sequence
-            } else {
-                self.sequence
-            },
-            fractal_depth: self.fractal_depth.unwrap_or(3),
-            chunk_size: self.chunk_size.unwrap_or(64),
+            sequence,
+            fractal_depth,
+            chunk_size,
             use_zippering: self.use_zippering,
+            parallel_chunk_size: self.parallel_chunk_size,
         }
     }
 }
@@ -110,6 +197,7 @@ mod tests {
         assert_eq!(config.fractal_depth, 3);
         assert_eq!(config.chunk_size, 64);
         assert!(!config.use_zippering);
+        assert_eq!(config.parallel_chunk_size(), None);
     }
 
     #[test]
@@ -127,5 +215,22 @@ mod tests {
         assert_eq!(config.fractal_depth, 2);
         assert_eq!(config.chunk_size, 128);
         assert!(config.use_zippering);
+
+        // Fractal layers pick up parameters set after they were added
+        let fractal = &config.layers()[0];
+        assert_eq!(fractal.id(), "fractal");
+        assert_eq!(fractal.params().get("depth"), Some("2"));
+        assert_eq!(fractal.params().get("chunk"), Some("128"));
+    }
+
+    #[test]
+    fn test_custom_layer_in_builder() {
+        let config = LayerConfig::builder()
+            .add_layer(FractalLayer::with_params(5, 16))
+            .add_aes()
+            .build();
+
+        assert_eq!(config.layers()[0].params().get("depth"), Some("5"));
+        assert_eq!(config.layers()[1].id(), "aes");
     }
 }
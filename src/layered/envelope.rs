@@ -0,0 +1,422 @@
+use std::io::Read;
+use std::sync::Arc;
+use crate::{Result, FskcError};
+use crate::utils::Reader;
+use super::config::LayerConfig;
+use super::keys::{KEY_ID_LEN, MESSAGE_NONCE_LEN};
+use super::layer::{Layer, LayerParams};
+use super::registry::LayerRegistry;
+use super::stream::valid_segment_size;
+
+/// Magic bytes at the start of every envelope
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"FSKC";
+
+/// Envelope format version written by this release
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Upper bound on the encoded header size
+const MAX_HEADER_LEN: usize = 64 * 1024;
+
+const FLAG_ZIPPERING: u8 = 0x01;
+const FLAG_STREAM: u8 = 0x02;
+const FLAG_PARALLEL: u8 = 0x04;
+
+/// Length of magic, version and header length
+const PREFIX_LEN: usize = 9;
+
+/// Self-describing header in front of every LayeredCrypto ciphertext
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvelopeHeader {
+    /// Format version the envelope was written with
+    pub version: u8,
+    /// Layer configuration used to encrypt the body
+    pub config: LayerConfig,
+    /// Identifier of the master key the body was encrypted under
+    pub key_id: [u8; KEY_ID_LEN],
+    /// Per-message nonce for the layer key schedule
+    pub message_nonce: [u8; MESSAGE_NONCE_LEN],
+    /// Plaintext segment size if the body is a segmented stream
+    pub segment_size: Option<usize>,
+}
+
+impl EnvelopeHeader {
+    /// Creates a header for the current format version
+    pub fn new(
+        config: LayerConfig,
+        key_id: [u8; KEY_ID_LEN],
+        message_nonce: [u8; MESSAGE_NONCE_LEN],
+    ) -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            config,
+            key_id,
+            message_nonce,
+            segment_size: None,
+        }
+    }
+
+    /// Serializes the header
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        if self.version != ENVELOPE_VERSION {
+            return Err(FskcError::UnsupportedVersion(self.version));
+        }
+
+        let sequence = &self.config.sequence;
+        if sequence.len() > u8::MAX as usize {
+            return Err(FskcError::FormatError("Too many layers".into()));
+        }
+
+        let mut out = Vec::with_capacity(64);
+        out.extend_from_slice(&ENVELOPE_MAGIC);
+        out.push(self.version);
+        out.extend_from_slice(&[0u8; 4]); // Header length, filled in below
+        let mut flags = 0;
+        if self.config.use_zippering {
+            flags |= FLAG_ZIPPERING;
+        }
+        if self.segment_size.is_some() {
+            flags |= FLAG_STREAM;
+        }
+        if self.config.parallel_chunk_size.is_some() {
+            flags |= FLAG_PARALLEL;
+        }
+        out.push(flags);
+        out.extend_from_slice(&encode_u32(self.config.fractal_depth)?);
+        out.extend_from_slice(&encode_u32(self.config.chunk_size)?);
+        out.push(sequence.len() as u8);
+        for layer in sequence {
+            encode_layer(&mut out, layer.as_ref())?;
+        }
+        out.extend_from_slice(&self.key_id);
+        out.extend_from_slice(&self.message_nonce);
+        if let Some(segment_size) = self.segment_size {
+            out.extend_from_slice(&encode_u32(segment_size)?);
+        }
+        if let Some(chunk_size) = self.config.parallel_chunk_size {
+            out.extend_from_slice(&encode_u32(chunk_size)?);
+        }
+
+        if out.len() > MAX_HEADER_LEN {
+            return Err(FskcError::FormatError("Header too large".into()));
+        }
+        let header_len = (out.len() as u32).to_le_bytes();
+        out[ENVELOPE_MAGIC.len() + 1..PREFIX_LEN].copy_from_slice(&header_len);
+        Ok(out)
+    }
+
+    /// Reads exactly one header from a stream, returning it with its raw bytes
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<(Self, Vec<u8>)> {
+        Self::read_from_with(reader, &LayerRegistry::default())
+    }
+
+    /// Reads one header from a stream, rebuilding layers from `registry`
+    pub fn read_from_with<R: Read>(
+        reader: &mut R,
+        registry: &LayerRegistry,
+    ) -> Result<(Self, Vec<u8>)> {
+        let mut raw = vec![0u8; ENVELOPE_MAGIC.len() + 1];
+        read_header_bytes(reader, &mut raw)?;
+        if raw[..ENVELOPE_MAGIC.len()] != ENVELOPE_MAGIC {
+            return Err(FskcError::FormatError("Missing envelope magic".into()));
+        }
+
+        let version = raw[ENVELOPE_MAGIC.len()];
+        if version != ENVELOPE_VERSION {
+            return Err(FskcError::UnsupportedVersion(version));
+        }
+
+        raw.resize(PREFIX_LEN, 0);
+        read_header_bytes(reader, &mut raw[ENVELOPE_MAGIC.len() + 1..])?;
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&raw[ENVELOPE_MAGIC.len() + 1..]);
+        let total = u32::from_le_bytes(len) as usize;
+        if !(PREFIX_LEN..=MAX_HEADER_LEN).contains(&total) {
+            return Err(FskcError::FormatError("Invalid header length".into()));
+        }
+
+        let read = raw.len();
+        raw.resize(total, 0);
+        read_header_bytes(reader, &mut raw[read..])?;
+
+        let (header, rest) = Self::decode_with(&raw, registry)?;
+        if !rest.is_empty() {
+            return Err(FskcError::FormatError("Unexpected header length".into()));
+        }
+        Ok((header, raw))
+    }
+
+    /// Parses a header, returning it together with the remaining body
+    pub fn decode(data: &[u8]) -> Result<(Self, &[u8])> {
+        Self::decode_with(data, &LayerRegistry::default())
+    }
+
+    /// Parses a header, rebuilding layers from `registry`
+    pub fn decode_with<'a>(data: &'a [u8], registry: &LayerRegistry) -> Result<(Self, &'a [u8])> {
+        let mut reader = Reader::new(data, "envelope");
+
+        if reader.take(ENVELOPE_MAGIC.len())? != ENVELOPE_MAGIC {
+            return Err(FskcError::FormatError("Missing envelope magic".into()));
+        }
+
+        let version = reader.u8()?;
+        if version != ENVELOPE_VERSION {
+            return Err(FskcError::UnsupportedVersion(version));
+        }
+        let header_len = reader.u32()? as usize;
+        let (header, rest) = Self::decode_fields(reader, version, registry)?;
+        if data.len() - rest.len() != header_len {
+            return Err(FskcError::FormatError("Header length mismatch".into()));
+        }
+        Ok((header, rest))
+    }
+
+    fn decode_fields<'a>(
+        mut reader: Reader<'a>,
+        version: u8,
+        registry: &LayerRegistry,
+    ) -> Result<(Self, &'a [u8])> {
+        let flags = reader.u8()?;
+        if flags & !(FLAG_ZIPPERING | FLAG_STREAM | FLAG_PARALLEL) != 0 {
+            return Err(FskcError::FormatError(format!("Unknown flags {:#04x}", flags)));
+        }
+
+        let fractal_depth = reader.u32()? as usize;
+        let chunk_size = reader.u32()? as usize;
+        if fractal_depth == 0 || chunk_size == 0 {
+            return Err(FskcError::FormatError("Invalid fractal parameters".into()));
+        }
+
+        let count = reader.u8()? as usize;
+        if count == 0 {
+            return Err(FskcError::FormatError("Empty layer sequence".into()));
+        }
+
+        let mut sequence = Vec::with_capacity(count);
+        for _ in 0..count {
+            sequence.push(decode_layer(&mut reader, registry)?);
+        }
+
+        let key_id = reader.array()?;
+        let message_nonce = reader.array()?;
+
+        let segment_size = if flags & FLAG_STREAM != 0 {
+            Some(reader.u32()? as usize)
+        } else {
+            None
+        };
+
+        let parallel_chunk_size = if flags & FLAG_PARALLEL != 0 {
+            match reader.u32()? {
+                0 => return Err(FskcError::FormatError("Zero parallel chunk size".into())),
+                size => Some(size as usize),
+            }
+        } else {
+            None
+        };
+
+        let header = Self {
+            version,
+            config: LayerConfig {
+                sequence,
+                fractal_depth,
+                chunk_size,
+                use_zippering: flags & FLAG_ZIPPERING != 0,
+                parallel_chunk_size,
+            },
+            key_id,
+            message_nonce,
+            segment_size,
+        };
+
+        // The segment size bounds every frame a reader allocates
+        if let Some(size) = segment_size {
+            if !valid_segment_size(&header.config, size) {
+                return Err(FskcError::FormatError(format!("Invalid segment size {}", size)));
+            }
+        }
+        Ok((header, reader.rest()))
+    }
+}
+
+/// Writes a layer as identifier followed by its parameters
+fn encode_layer(out: &mut Vec<u8>, layer: &dyn Layer) -> Result<()> {
+    super::registry::validate_id(layer.id())?;
+    out.push(layer.id().len() as u8);
+    out.extend_from_slice(layer.id().as_bytes());
+
+    let params = layer.params();
+    if params.len() > u8::MAX as usize {
+        return Err(FskcError::FormatError("Too many layer parameters".into()));
+    }
+    out.push(params.len() as u8);
+    for (key, value) in params.iter() {
+        if key.is_empty() || key.len() > u8::MAX as usize || value.len() > u16::MAX as usize {
+            return Err(FskcError::FormatError(format!("Invalid layer parameter '{}'", key)));
+        }
+        out.push(key.len() as u8);
+        out.extend_from_slice(key.as_bytes());
+        out.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        out.extend_from_slice(value.as_bytes());
+    }
+    Ok(())
+}
+
+fn decode_layer(reader: &mut Reader<'_>, registry: &LayerRegistry) -> Result<Arc<dyn Layer>> {
+    let id_len = reader.u8()? as usize;
+    let id = reader.string(id_len)?;
+
+    let count = reader.u8()? as usize;
+    let mut params = LayerParams::new();
+    for _ in 0..count {
+        let key_len = reader.u8()? as usize;
+        let key = reader.string(key_len)?;
+        let value_len = reader.u16()? as usize;
+        let value = reader.string(value_len)?;
+        if params.get(&key).is_some() {
+            return Err(FskcError::FormatError(format!("Duplicate layer parameter '{}'", key)));
+        }
+        params.insert(key, value);
+    }
+
+    registry.build(&id, &params)
+}
+
+fn read_header_bytes<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => FskcError::FormatError("Truncated envelope".into()),
+        _ => FskcError::IoError(e),
+    })
+}
+
+fn encode_u32(value: usize) -> Result<[u8; 4]> {
+    u32::try_from(value)
+        .map(u32::to_le_bytes)
+        .map_err(|_| FskcError::FormatError(format!("Parameter {} out of range", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_header() -> EnvelopeHeader {
+        let config = LayerConfig::builder()
+            .add_fractal()
+            .add_aes()
+            .add_chacha()
+            .fractal_depth(2)
+            .chunk_size(128)
+            .enable_zippering()
+            .build();
+        EnvelopeHeader::new(config, [1; KEY_ID_LEN], [2; MESSAGE_NONCE_LEN])
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let header = sample_header();
+        let mut bytes = header.encode().unwrap();
+        bytes.extend_from_slice(b"body");
+
+        let (decoded, body) = EnvelopeHeader::decode(&bytes).unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(body, b"body");
+    }
+
+    #[test]
+    fn test_rejects_malformed_headers() {
+        let bytes = sample_header().encode().unwrap();
+
+        for len in 0..bytes.len() {
+            assert!(EnvelopeHeader::decode(&bytes[..len]).is_err());
+        }
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 0xff;
+        assert!(matches!(
+            EnvelopeHeader::decode(&bad_magic),
+            Err(FskcError::FormatError(_))
+        ));
+
+        let mut unknown_flag = bytes.clone();
+        unknown_flag[PREFIX_LEN] |= 0x80;
+        assert!(EnvelopeHeader::decode(&unknown_flag).is_err());
+
+        let mut wrong_len = bytes.clone();
+        wrong_len[ENVELOPE_MAGIC.len() + 1] += 1;
+        assert!(EnvelopeHeader::decode(&wrong_len).is_err());
+
+        let mut future = bytes.clone();
+        future[4] = 0xfe;
+        assert!(matches!(
+            EnvelopeHeader::decode(&future),
+            Err(FskcError::UnsupportedVersion(0xfe))
+        ));
+    }
+
+    #[test]
+    fn test_read_stream_header() {
+        let header = EnvelopeHeader {
+            segment_size: Some(4096),
+            ..sample_header()
+        };
+        let mut bytes = header.encode().unwrap();
+        let header_len = bytes.len();
+        bytes.extend_from_slice(b"segments");
+
+        let mut reader = &bytes[..];
+        let (decoded, raw) = EnvelopeHeader::read_from(&mut reader).unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(raw.len(), header_len);
+        assert_eq!(reader, b"segments");
+    }
+
+    #[test]
+    fn test_parallel_chunk_size_roundtrip() {
+        let mut header = sample_header();
+        header.config.parallel_chunk_size = Some(4096);
+        header.segment_size = Some(65536);
+
+        let bytes = header.encode().unwrap();
+        let (decoded, body) = EnvelopeHeader::decode(&bytes).unwrap();
+        assert_eq!(decoded, header);
+        assert!(body.is_empty());
+
+        let mut reader = &bytes[..];
+        assert_eq!(EnvelopeHeader::read_from(&mut reader).unwrap().0, header);
+    }
+
+    #[test]
+    fn test_unknown_layer_needs_registry() {
+        #[derive(Debug)]
+        struct Identity;
+
+        impl Layer for Identity {
+            fn id(&self) -> &str {
+                "identity"
+            }
+
+            fn params(&self) -> LayerParams {
+                LayerParams::new().with("note", "unused")
+            }
+
+            fn encrypt(&self, _ctx: &super::super::LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+                Ok(data)
+            }
+
+            fn decrypt(&self, _ctx: &super::super::LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+                Ok(data)
+            }
+        }
+
+        let config = LayerConfig::builder().add_layer(Identity).add_aes().build();
+        let bytes = EnvelopeHeader::new(config.clone(), [1; KEY_ID_LEN], [2; MESSAGE_NONCE_LEN])
+            .encode()
+            .unwrap();
+
+        assert!(EnvelopeHeader::decode(&bytes).is_err());
+
+        let mut registry = LayerRegistry::new();
+        registry.register("identity", |_| Ok(Arc::new(Identity))).unwrap();
+        let (decoded, _) = EnvelopeHeader::decode_with(&bytes, &registry).unwrap();
+        assert_eq!(decoded.config, config);
+    }
+}
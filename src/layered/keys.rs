@@ -0,0 +1,138 @@
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
+use crate::Key256;
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Length of the key identifier written in front of every ciphertext
+pub const KEY_ID_LEN: usize = 8;
+
+/// Length of the per-message nonce written in front of every ciphertext
+pub const MESSAGE_NONCE_LEN: usize = 16;
+
+/// Key and nonce used by a single layer for a single message
+///
+/// Wiped when dropped.
+pub struct LayerKey {
+    /// 256-bit layer key
+    pub key: [u8; 32],
+    /// 96-bit layer nonce
+    pub nonce: [u8; 12],
+    /// 192-bit layer nonce for extended-nonce ciphers
+    pub extended_nonce: [u8; 24],
+}
+
+/// Master secret from which every layer key is derived
+#[derive(Clone)]
+pub(crate) struct MasterKey {
+    bytes: [u8; 32],
+}
+
+impl MasterKey {
+    /// Uses caller-supplied key material as the master key
+    pub fn new(key: &Key256) -> Self {
+        Self { bytes: *key.as_bytes() }
+    }
+
+    /// Returns the public identifier of this key
+    pub fn key_id(&self) -> [u8; KEY_ID_LEN] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"fskc-key-id");
+        hasher.update(self.bytes);
+
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&hasher.finalize()[..KEY_ID_LEN]);
+        id
+    }
+
+    /// Derives the key and nonce for the layer at `index` of a message
+    pub fn layer_key(&self, index: usize, message_nonce: &[u8; MESSAGE_NONCE_LEN]) -> LayerKey {
+        self.expand(b"fskc-layer", index as u64, message_nonce)
+    }
+
+    /// Derives the key and nonce sealing one segment of a stream
+    pub fn segment_key(&self, segment_nonce: &[u8; MESSAGE_NONCE_LEN]) -> LayerKey {
+        self.expand(b"fskc-segment", 0, segment_nonce)
+    }
+
+    fn expand(&self, label: &[u8], index: u64, salt: &[u8; MESSAGE_NONCE_LEN]) -> LayerKey {
+        let hkdf = Hkdf::<Sha256>::new(Some(salt), &self.bytes);
+
+        let mut info = Vec::with_capacity(label.len() + 8);
+        info.extend_from_slice(label);
+        info.extend_from_slice(&index.to_le_bytes());
+
+        // The 96-bit nonce is a prefix of the extended nonce, so keys derived
+        // before extended nonces existed are unchanged
+        let mut okm = [0u8; 56];
+        hkdf.expand(&info, &mut okm)
+            .expect("56 bytes is a valid HKDF-SHA256 output length");
+
+        // Filled in place so no stray copy of the key outlives this call
+        let mut layer_key = LayerKey {
+            key: [0u8; 32],
+            nonce: [0u8; 12],
+            extended_nonce: [0u8; 24],
+        };
+        layer_key.key.copy_from_slice(&okm[..32]);
+        layer_key.nonce.copy_from_slice(&okm[32..44]);
+        layer_key.extended_nonce.copy_from_slice(&okm[32..]);
+        okm.zeroize();
+        layer_key
+    }
+}
+
+impl Drop for MasterKey {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+impl Drop for LayerKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.nonce.zeroize();
+        self.extended_nonce.zeroize();
+    }
+}
+
+impl fmt::Debug for LayerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LayerKey(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_keys_are_distinct() {
+        let master = MasterKey::new(&Key256::insecure_from_u64(67890));
+        let nonce = [7u8; MESSAGE_NONCE_LEN];
+
+        let first = master.layer_key(0, &nonce);
+        let second = master.layer_key(1, &nonce);
+        assert_ne!(first.key, second.key);
+        assert_ne!(first.nonce, second.nonce);
+
+        let other_message = master.layer_key(0, &[8u8; MESSAGE_NONCE_LEN]);
+        assert_ne!(first.key, other_message.key);
+
+        let again = master.layer_key(0, &nonce);
+        assert_eq!(first.key, again.key);
+        assert_eq!(first.nonce, again.nonce);
+    }
+
+    #[test]
+    fn test_key_id_depends_on_master() {
+        assert_eq!(
+            MasterKey::new(&Key256::insecure_from_u64(1)).key_id(),
+            MasterKey::new(&Key256::insecure_from_u64(1)).key_id()
+        );
+        assert_ne!(
+            MasterKey::new(&Key256::insecure_from_u64(1)).key_id(),
+            MasterKey::new(&Key256::insecure_from_u64(2)).key_id()
+        );
+    }
+}
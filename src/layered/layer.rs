@@ -1,165 +1,774 @@
+use std::fmt;
+use std::str::FromStr;
 use std::sync::Arc;
-use crate::{Result, FskcError, FractalNode};
+use crate::{Result, FskcError, FractalNode, Key256, Permutation};
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
 use chacha20::ChaCha20;
-use chacha20::cipher::{KeyIvInit, StreamCipher};
-use rand::RngCore;
+use chacha20::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305, XNonce};
+use rayon::prelude::*;
+use super::keys::LayerKey;
 
-/// Represents different types of encryption layers
-#[derive(Clone)]
-pub enum Layer {
-    Fractal(FractalLayer),
-    Symmetric(SymmetricLayer),
+/// An encryption layer that can be composed into a [`LayerConfig`](super::LayerConfig)
+///
+/// Layers are identified in envelopes by [`Layer::id`] and rebuilt from
+/// [`Layer::params`] through a [`LayerRegistry`](super::LayerRegistry), so a
+/// layer must be fully described by its identifier and parameters.
+pub trait Layer: fmt::Debug + Send + Sync {
+    /// Stable identifier written into envelopes
+    fn id(&self) -> &str;
+
+    /// Parameters needed to rebuild this layer
+    fn params(&self) -> LayerParams {
+        LayerParams::new()
+    }
+
+    /// Maximum number of bytes this layer adds to its input
+    fn overhead(&self) -> usize {
+        0
+    }
+
+    /// Encrypts data with the key material for this layer
+    fn encrypt(&self, ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>>;
+
+    /// Decrypts data with the key material for this layer
+    fn decrypt(&self, ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>>;
+}
+
+/// Key material handed to a layer for one message
+pub struct LayerContext<'a> {
+    /// Key and nonce derived for this layer and message
+    pub key: LayerKey,
+    /// Seed supplied by the caller of encrypt or decrypt
+    pub initial_seed: &'a Key256,
+    /// Data that authenticated layers must bind without encrypting
+    pub associated_data: &'a [u8],
+    /// Chunk size for layers that support chunk-parallel processing
+    pub parallel_chunk_size: Option<usize>,
+}
+
+impl LayerContext<'_> {
+    /// Returns a seed bound to both the layer key and the initial seed
+    pub fn seed(&self) -> Key256 {
+        Key256::from_bytes(self.key.key).combine(self.initial_seed, b"fskc-layer-seed")
+    }
+}
+
+/// Ordered key/value parameters describing a layer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayerParams {
+    entries: Vec<(String, String)>,
+}
+
+impl LayerParams {
+    /// Creates an empty parameter set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a parameter, replacing any previous value for the key
+    pub fn with(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    /// Sets a parameter, replacing any previous value for the key
+    pub fn insert(&mut self, key: impl Into<String>, value: impl ToString) {
+        let key = key.into();
+        let value = value.to_string();
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    /// Returns the raw value of a parameter
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parses a required parameter
+    pub fn parse<T: FromStr>(&self, key: &str) -> Result<T> {
+        let value = self.get(key).ok_or_else(|| {
+            FskcError::FormatError(format!("Missing layer parameter '{}'", key))
+        })?;
+        value.parse().map_err(|_| {
+            FskcError::FormatError(format!("Invalid value '{}' for layer parameter '{}'", value, key))
+        })
+    }
+
+    /// Iterates over the parameters in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the number of parameters
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no parameters
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 }
 
 /// Represents a fractal-based encryption layer
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FractalLayer {
-    // Additional configuration could be added here
+    depth: usize,
+    chunk_size: usize,
+    shuffle_chunks: bool,
+}
+
+impl Default for FractalLayer {
+    fn default() -> Self {
+        Self::with_params(3, 64)
+    }
 }
 
 impl FractalLayer {
+    /// Identifier of the fractal layer
+    pub const ID: &'static str = "fractal";
+
+    /// Creates a fractal layer with the default depth and chunk size
     pub fn new() -> Self {
-        Self {}
+        Self::default()
     }
 
-    pub fn generate(
-        &self,
-        data: Vec<u8>,
-        seed: u64,
-        depth: usize,
-        chunk_size: usize,
-    ) -> Result<Arc<FractalNode>> {
-        FractalNode::generate(data, seed, depth, chunk_size)
+    /// Creates a fractal layer with the given depth and chunk size
+    pub fn with_params(depth: usize, chunk_size: usize) -> Self {
+        Self { depth, chunk_size, shuffle_chunks: false }
+    }
+
+    /// Emits the leaf chunks in a keyed order instead of in sequence
+    pub fn shuffled(mut self) -> Self {
+        self.shuffle_chunks = true;
+        self
+    }
+
+    /// Rebuilds a fractal layer from its parameters
+    pub fn from_params(params: &LayerParams) -> Result<Arc<dyn Layer>> {
+        let depth = params.parse("depth")?;
+        let chunk_size = params.parse("chunk")?;
+        if depth == 0 || chunk_size == 0 {
+            return Err(FskcError::FormatError("Invalid fractal parameters".into()));
+        }
+        let layer = Self::with_params(depth, chunk_size);
+        match params.get("shuffle") {
+            Some(_) if params.parse("shuffle")? => Ok(Arc::new(layer.shuffled())),
+            _ => Ok(Arc::new(layer)),
+        }
+    }
+
+    /// Returns the fractal depth
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the chunk size
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Generates the fractal tree for data
+    pub fn generate(&self, data: Vec<u8>, seed: &Key256) -> Result<Arc<FractalNode>> {
+        FractalNode::generate(data, seed, self.depth, self.chunk_size)
+    }
+
+    /// Encrypts data into the serialized leaf values of a fractal tree
+    pub fn encrypt_with_seed(&self, data: Vec<u8>, seed: &Key256) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(data);
+        }
+        let tree = FractalNode::generate_compact(data, seed, self.depth, self.chunk_size)?;
+        if !self.shuffle_chunks {
+            return Ok(tree.ciphertext());
+        }
+        tree.ordered_ciphertext(&self.chunk_order(seed, tree.len())?)
+    }
+
+    /// Parses serialized leaf values back into a tree and decrypts it
+    pub fn decrypt_with_seed(&self, data: Vec<u8>, seed: &Key256) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(data);
+        }
+        if !self.shuffle_chunks {
+            return FractalNode::from_ciphertext(data, seed, self.depth, self.chunk_size)?.decrypt();
+        }
+        let order = self.chunk_order(seed, data.len())?;
+        FractalNode::from_ordered_ciphertext(data, &order, seed, self.depth, self.chunk_size)?.decrypt()
+    }
+
+    /// Keyed order of the leaf chunks of a tree over `len` bytes
+    fn chunk_order(&self, seed: &Key256, len: usize) -> Result<Permutation> {
+        let leaves = FractalNode::leaf_count_for(len, self.depth, self.chunk_size);
+        Permutation::new(&seed.derive(b"fskc-chunk-order"), leaves)
+    }
+}
+
+impl Layer for FractalLayer {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn params(&self) -> LayerParams {
+        let params = LayerParams::new()
+            .with("depth", self.depth)
+            .with("chunk", self.chunk_size);
+        if self.shuffle_chunks {
+            params.with("shuffle", true)
+        } else {
+            params
+        }
+    }
+
+    fn encrypt(&self, ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+        self.encrypt_with_seed(data, &ctx.seed())
+    }
+
+    fn decrypt(&self, ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+        self.decrypt_with_seed(data, &ctx.seed())
+    }
+}
+
+/// Keyed transposition of the bytes of each message
+///
+/// Reorders bytes with a [`Permutation`] derived from the layer seed and
+/// the message length. It hides byte positions but not byte values, so it
+/// is meant to be combined with a cipher layer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranspositionLayer;
+
+impl TranspositionLayer {
+    /// Identifier of the transposition layer
+    pub const ID: &'static str = "transpose";
+
+    /// Rebuilds a transposition layer from its parameters
+    pub fn from_params(_params: &LayerParams) -> Result<Arc<dyn Layer>> {
+        Ok(Arc::new(Self))
+    }
+
+    fn permutation(ctx: &LayerContext, len: usize) -> Result<Permutation> {
+        Permutation::new(&ctx.seed().derive(b"fskc-transpose"), len)
+    }
+}
+
+impl Layer for TranspositionLayer {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn encrypt(&self, ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+        Self::permutation(ctx, data.len())?.permute(&data)
+    }
+
+    fn decrypt(&self, ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+        Self::permutation(ctx, data.len())?.unpermute(&data)
     }
 }
 
 /// Available symmetric encryption algorithms
-#[derive(Clone)]
+///
+/// `ChaCha` is the unauthenticated ChaCha20 stream cipher and ignores
+/// associated data; the other variants are AEADs and bind it.
+#[derive(Debug, Clone, PartialEq)]
 pub enum SymmetricLayer {
     Aes,
     ChaCha,
+    ChaChaPoly,
+    XChaChaPoly,
 }
 
+/// Length of the authentication tag added by the AEAD variants
+const AEAD_TAG_LEN: usize = 16;
+
 impl SymmetricLayer {
-    pub fn encrypt(&self, rng: &mut impl RngCore, data: &[u8]) -> Result<Vec<u8>> {
-        match self {
-            SymmetricLayer::Aes => {
-                // Generate key and nonce
-                let mut key = [0u8; 32];
-                rng.fill_bytes(&mut key);
-                let mut nonce_bytes = [0u8; 12];
-                rng.fill_bytes(&mut nonce_bytes);
-
-                // Create cipher and encrypt
-                let cipher = Aes256Gcm::new_from_slice(&key)
-                    .map_err(|e| FskcError::EncryptionError(e.to_string()))?;
-                let nonce = Nonce::from_slice(&nonce_bytes);
-
-                // Prepend key and nonce to encrypted data
-                let mut result = Vec::with_capacity(44 + data.len());
-                result.extend_from_slice(&key);
-                result.extend_from_slice(&nonce_bytes);
-                result.extend(
-                    cipher
-                        .encrypt(nonce, data)
-                        .map_err(|e| FskcError::EncryptionError(e.to_string()))?
-                );
-                Ok(result)
-            }
-            SymmetricLayer::ChaCha => {
-                // Generate key and nonce
-                let mut key = [0u8; 32];
-                rng.fill_bytes(&mut key);
-                let mut nonce_bytes = [0u8; 12];
-                rng.fill_bytes(&mut nonce_bytes);
+    /// Identifier of the AES-256-GCM layer
+    pub const AES_ID: &'static str = "aes";
+
+    /// Identifier of the ChaCha20 layer
+    pub const CHACHA_ID: &'static str = "chacha";
+
+    /// Identifier of the ChaCha20-Poly1305 layer
+    pub const CHACHA_POLY_ID: &'static str = "chacha20poly1305";
+
+    /// Identifier of the XChaCha20-Poly1305 layer
+    pub const XCHACHA_POLY_ID: &'static str = "xchacha20poly1305";
+
+    /// Rebuilds the AES-256-GCM layer
+    pub fn aes_from_params(_params: &LayerParams) -> Result<Arc<dyn Layer>> {
+        Ok(Arc::new(SymmetricLayer::Aes))
+    }
+
+    /// Rebuilds the ChaCha20 layer
+    pub fn chacha_from_params(_params: &LayerParams) -> Result<Arc<dyn Layer>> {
+        Ok(Arc::new(SymmetricLayer::ChaCha))
+    }
+
+    /// Rebuilds the ChaCha20-Poly1305 layer
+    pub fn chacha_poly_from_params(_params: &LayerParams) -> Result<Arc<dyn Layer>> {
+        Ok(Arc::new(SymmetricLayer::ChaChaPoly))
+    }
+
+    /// Rebuilds the XChaCha20-Poly1305 layer
+    pub fn xchacha_poly_from_params(_params: &LayerParams) -> Result<Arc<dyn Layer>> {
+        Ok(Arc::new(SymmetricLayer::XChaChaPoly))
+    }
 
-                // Create cipher
+    /// Returns true if the algorithm authenticates its ciphertext
+    pub fn is_authenticated(&self) -> bool {
+        !matches!(self, SymmetricLayer::ChaCha)
+    }
+
+    /// Encrypts data under a derived layer key, binding `associated_data`
+    pub fn encrypt_with_key(
+        &self,
+        key: &LayerKey,
+        data: &[u8],
+        associated_data: &[u8],
+    ) -> Result<Vec<u8>> {
+        let payload = Payload { msg: data, aad: associated_data };
+        let sealed = match self {
+            SymmetricLayer::Aes => Aes256Gcm::new_from_slice(&key.key)
+                .map_err(|e| FskcError::EncryptionError(e.to_string()))?
+                .encrypt(Nonce::from_slice(&key.nonce), payload),
+            SymmetricLayer::ChaChaPoly => ChaCha20Poly1305::new_from_slice(&key.key)
+                .map_err(|e| FskcError::EncryptionError(e.to_string()))?
+                .encrypt(Nonce::from_slice(&key.nonce), payload),
+            SymmetricLayer::XChaChaPoly => XChaCha20Poly1305::new_from_slice(&key.key)
+                .map_err(|e| FskcError::EncryptionError(e.to_string()))?
+                .encrypt(XNonce::from_slice(&key.extended_nonce), payload),
+            SymmetricLayer::ChaCha => {
                 let mut cipher = ChaCha20::new(
-                    key.as_slice().into(),
-                    nonce_bytes.as_slice().into(),
+                    key.key.as_slice().into(),
+                    key.nonce.as_slice().into(),
                 );
 
-                // Encrypt data
                 let mut encrypted = data.to_vec();
                 cipher.apply_keystream(&mut encrypted);
+                return Ok(encrypted);
+            }
+        };
+
+        sealed.map_err(|e| FskcError::EncryptionError(e.to_string()))
+    }
 
-                // Prepend key and nonce to encrypted data
-                let mut result = Vec::with_capacity(44 + encrypted.len());
-                result.extend_from_slice(&key);
-                result.extend_from_slice(&nonce_bytes);
-                result.extend(encrypted);
-                Ok(result)
+    /// Decrypts data under a derived layer key, checking `associated_data`
+    pub fn decrypt_with_key(
+        &self,
+        key: &LayerKey,
+        data: &[u8],
+        associated_data: &[u8],
+    ) -> Result<Vec<u8>> {
+        let payload = Payload { msg: data, aad: associated_data };
+        let opened = match self {
+            SymmetricLayer::Aes => Aes256Gcm::new_from_slice(&key.key)
+                .map_err(|e| FskcError::DecryptionError(e.to_string()))?
+                .decrypt(Nonce::from_slice(&key.nonce), payload),
+            SymmetricLayer::ChaChaPoly => ChaCha20Poly1305::new_from_slice(&key.key)
+                .map_err(|e| FskcError::DecryptionError(e.to_string()))?
+                .decrypt(Nonce::from_slice(&key.nonce), payload),
+            SymmetricLayer::XChaChaPoly => XChaCha20Poly1305::new_from_slice(&key.key)
+                .map_err(|e| FskcError::DecryptionError(e.to_string()))?
+                .decrypt(XNonce::from_slice(&key.extended_nonce), payload),
+            SymmetricLayer::ChaCha => {
+                let mut cipher = ChaCha20::new(
+                    key.key.as_slice().into(),
+                    key.nonce.as_slice().into(),
+                );
+
+                let mut decrypted = data.to_vec();
+                cipher.apply_keystream(&mut decrypted);
+                return Ok(decrypted);
             }
+        };
+
+        opened.map_err(|e| FskcError::DecryptionError(e.to_string()))
+    }
+
+    /// Encrypts data in independently keyed chunks of `chunk_size` bytes,
+    /// processed in parallel
+    ///
+    /// Authenticated variants seal every chunk under its own nonce and mark
+    /// the final chunk, so chunks cannot be reordered or dropped. The raw
+    /// ChaCha20 variant produces the same output as
+    /// [`SymmetricLayer::encrypt_with_key`].
+    pub fn encrypt_chunks(
+        &self,
+        key: &LayerKey,
+        data: &[u8],
+        associated_data: &[u8],
+        chunk_size: usize,
+    ) -> Result<Vec<u8>> {
+        if chunk_size == 0 {
+            return Err(FskcError::InvalidDataSize(0));
+        }
+
+        if !self.is_authenticated() {
+            return Ok(chacha_chunks(key, data, chunk_size));
         }
+
+        // An empty message still gets one authenticated chunk
+        let chunks: Vec<&[u8]> = if data.is_empty() {
+            vec![data]
+        } else {
+            data.chunks(chunk_size).collect()
+        };
+        let last = check_chunk_count(chunks.len())?;
+
+        let sealed = chunks
+            .par_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let aad = chunk_associated_data(associated_data, i == last);
+                self.encrypt_with_key(&chunk_key(key, i), chunk, &aad)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(sealed.concat())
     }
 
-    pub fn decrypt(&self, _rng: &mut impl RngCore, data: &[u8]) -> Result<Vec<u8>> {
-        if data.len() < 44 {
-            return Err(FskcError::EncryptionError("Invalid data length".into()));
+    /// Decrypts the output of [`SymmetricLayer::encrypt_chunks`]
+    pub fn decrypt_chunks(
+        &self,
+        key: &LayerKey,
+        data: &[u8],
+        associated_data: &[u8],
+        chunk_size: usize,
+    ) -> Result<Vec<u8>> {
+        if chunk_size == 0 {
+            return Err(FskcError::InvalidDataSize(0));
         }
 
-        // Split data into key, nonce, and ciphertext
-        let (key, rest) = data.split_at(32);
-        let (nonce_bytes, ciphertext) = rest.split_at(12);
+        if !self.is_authenticated() {
+            return Ok(chacha_chunks(key, data, chunk_size));
+        }
+
+        let chunks: Vec<&[u8]> = data.chunks(chunk_size + AEAD_TAG_LEN).collect();
+        if chunks.last().is_none_or(|chunk| chunk.len() < AEAD_TAG_LEN) {
+            return Err(FskcError::DecryptionError("Truncated chunk".into()));
+        }
+        let last = check_chunk_count(chunks.len())?;
 
+        let opened = chunks
+            .par_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let aad = chunk_associated_data(associated_data, i == last);
+                self.decrypt_with_key(&chunk_key(key, i), chunk, &aad)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(opened.concat())
+    }
+}
+
+impl Layer for SymmetricLayer {
+    fn id(&self) -> &str {
         match self {
-            SymmetricLayer::Aes => {
-                // Create cipher and decrypt
-                let cipher = Aes256Gcm::new_from_slice(key)
-                    .map_err(|e| FskcError::EncryptionError(e.to_string()))?;
-                let nonce = Nonce::from_slice(nonce_bytes);
-
-                cipher
-                    .decrypt(nonce, ciphertext)
-                    .map_err(|e| FskcError::EncryptionError(e.to_string()))
-            }
-            SymmetricLayer::ChaCha => {
-                // Create cipher
-                let mut cipher = ChaCha20::new(
-                    key.into(),
-                    nonce_bytes.into(),
-                );
+            SymmetricLayer::Aes => Self::AES_ID,
+            SymmetricLayer::ChaCha => Self::CHACHA_ID,
+            SymmetricLayer::ChaChaPoly => Self::CHACHA_POLY_ID,
+            SymmetricLayer::XChaChaPoly => Self::XCHACHA_POLY_ID,
+        }
+    }
 
-                // Decrypt data
-                let mut decrypted = ciphertext.to_vec();
-                cipher.apply_keystream(&mut decrypted);
-                Ok(decrypted)
-            }
+    fn overhead(&self) -> usize {
+        if self.is_authenticated() {
+            AEAD_TAG_LEN
+        } else {
+            0
         }
     }
+
+    fn encrypt(&self, ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+        match ctx.parallel_chunk_size {
+            Some(chunk_size) => self.encrypt_chunks(&ctx.key, &data, ctx.associated_data, chunk_size),
+            None => self.encrypt_with_key(&ctx.key, &data, ctx.associated_data),
+        }
+    }
+
+    fn decrypt(&self, ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+        match ctx.parallel_chunk_size {
+            Some(chunk_size) => self.decrypt_chunks(&ctx.key, &data, ctx.associated_data, chunk_size),
+            None => self.decrypt_with_key(&ctx.key, &data, ctx.associated_data),
+        }
+    }
+}
+
+/// Derives the key of one chunk by folding its index into the nonces
+fn chunk_key(key: &LayerKey, index: usize) -> LayerKey {
+    let counter = (index as u32).to_be_bytes();
+    let mut chunk = LayerKey {
+        key: key.key,
+        nonce: key.nonce,
+        extended_nonce: key.extended_nonce,
+    };
+    for (byte, c) in chunk.nonce[8..].iter_mut().zip(counter) {
+        *byte ^= c;
+    }
+    for (byte, c) in chunk.extended_nonce[20..].iter_mut().zip(counter) {
+        *byte ^= c;
+    }
+    chunk
+}
+
+/// Appends the final-chunk marker to the caller's associated data
+fn chunk_associated_data(associated_data: &[u8], last: bool) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(associated_data.len() + 1);
+    aad.extend_from_slice(associated_data);
+    aad.push(last as u8);
+    aad
+}
+
+/// Returns the index of the final chunk, rejecting counts the nonce cannot hold
+fn check_chunk_count(count: usize) -> Result<usize> {
+    if count > u32::MAX as usize + 1 {
+        return Err(FskcError::InvalidDataSize(count));
+    }
+    Ok(count - 1)
+}
+
+/// Applies the ChaCha20 keystream to chunks in parallel by seeking
+fn chacha_chunks(key: &LayerKey, data: &[u8], chunk_size: usize) -> Vec<u8> {
+    let mut output = data.to_vec();
+    output
+        .par_chunks_mut(chunk_size)
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            let mut cipher = ChaCha20::new(
+                key.key.as_slice().into(),
+                key.nonce.as_slice().into(),
+            );
+            cipher.seek((i * chunk_size) as u64);
+            cipher.apply_keystream(chunk);
+        });
+    output
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand::SeedableRng;
-    use rand_chacha::ChaCha20Rng;
+
+    const ALL_SYMMETRIC: [SymmetricLayer; 4] = [
+        SymmetricLayer::Aes,
+        SymmetricLayer::ChaCha,
+        SymmetricLayer::ChaChaPoly,
+        SymmetricLayer::XChaChaPoly,
+    ];
+
+    fn test_key() -> LayerKey {
+        LayerKey { key: [0x42; 32], nonce: [0x17; 12], extended_nonce: [0x17; 24] }
+    }
 
     #[test]
     fn test_symmetric_layers() {
         let data = b"Test symmetric encryption".to_vec();
-        let mut rng = ChaCha20Rng::seed_from_u64(12345);
+        let key = test_key();
 
-        for layer in [SymmetricLayer::Aes, SymmetricLayer::ChaCha] {
-            let mut rng_clone = rng.clone();
-            let encrypted = layer.encrypt(&mut rng, &data).unwrap();
-            let decrypted = layer.decrypt(&mut rng_clone, &encrypted).unwrap();
+        for layer in ALL_SYMMETRIC {
+            let encrypted = layer.encrypt_with_key(&key, &data, b"").unwrap();
+            assert_eq!(encrypted.len(), data.len() + layer.overhead());
+            assert_ne!(&encrypted[..data.len()], &data[..]);
+            let decrypted = layer.decrypt_with_key(&key, &encrypted, b"").unwrap();
             assert_eq!(data, decrypted);
         }
     }
 
+    #[test]
+    fn test_key_not_in_ciphertext() {
+        let data = b"Test symmetric encryption".to_vec();
+        let key = test_key();
+
+        for layer in ALL_SYMMETRIC {
+            let encrypted = layer.encrypt_with_key(&key, &data, b"").unwrap();
+            assert!(!encrypted.windows(32).any(|w| w == key.key));
+        }
+    }
+
+    #[test]
+    fn test_aead_layers_detect_tampering() {
+        let data = b"Test authenticated encryption".to_vec();
+        let key = test_key();
+
+        for layer in ALL_SYMMETRIC.into_iter().filter(SymmetricLayer::is_authenticated) {
+            let encrypted = layer.encrypt_with_key(&key, &data, b"pairlet-7").unwrap();
+            assert_eq!(layer.decrypt_with_key(&key, &encrypted, b"pairlet-7").unwrap(), data);
+
+            let mut flipped = encrypted.clone();
+            flipped[0] ^= 0x01;
+            assert!(layer.decrypt_with_key(&key, &flipped, b"pairlet-7").is_err());
+            assert!(layer.decrypt_with_key(&key, &encrypted, b"pairlet-8").is_err());
+            assert!(layer.decrypt_with_key(&key, &encrypted, b"").is_err());
+        }
+
+        // The raw stream cipher cannot notice a flipped bit
+        let encrypted = SymmetricLayer::ChaCha.encrypt_with_key(&key, &data, b"").unwrap();
+        let mut flipped = encrypted;
+        flipped[0] ^= 0x01;
+        let decrypted = SymmetricLayer::ChaCha.decrypt_with_key(&key, &flipped, b"").unwrap();
+        assert_eq!(decrypted[0], data[0] ^ 0x01);
+    }
+
+    #[test]
+    fn test_chunks_match_sequential_sealing() {
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let key = test_key();
+
+        for layer in ALL_SYMMETRIC {
+            for chunk_size in [1, 64, 999, 1000, 4096] {
+                let encrypted = layer.encrypt_chunks(&key, &data, b"ctx", chunk_size).unwrap();
+
+                // Reference: seal each chunk one after the other
+                let expected: Vec<u8> = if layer.is_authenticated() {
+                    let count = data.chunks(chunk_size).count();
+                    data.chunks(chunk_size)
+                        .enumerate()
+                        .flat_map(|(i, chunk)| {
+                            let aad = chunk_associated_data(b"ctx", i == count - 1);
+                            layer.encrypt_with_key(&chunk_key(&key, i), chunk, &aad).unwrap()
+                        })
+                        .collect()
+                } else {
+                    layer.encrypt_with_key(&key, &data, b"ctx").unwrap()
+                };
+                assert_eq!(encrypted, expected);
+
+                let decrypted = layer.decrypt_chunks(&key, &encrypted, b"ctx", chunk_size).unwrap();
+                assert_eq!(decrypted, data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunks_detect_reordering_and_truncation() {
+        let data = vec![0x5a; 256];
+        let key = test_key();
+        let sealed_chunk = 64 + AEAD_TAG_LEN;
+
+        for layer in ALL_SYMMETRIC.into_iter().filter(SymmetricLayer::is_authenticated) {
+            let encrypted = layer.encrypt_chunks(&key, &data, b"", 64).unwrap();
+            assert_eq!(encrypted.len(), 4 * sealed_chunk);
+
+            let mut swapped = encrypted.clone();
+            swapped[..sealed_chunk].copy_from_slice(&encrypted[sealed_chunk..2 * sealed_chunk]);
+            swapped[sealed_chunk..2 * sealed_chunk].copy_from_slice(&encrypted[..sealed_chunk]);
+            assert!(layer.decrypt_chunks(&key, &swapped, b"", 64).is_err());
+
+            let truncated = &encrypted[..3 * sealed_chunk];
+            assert!(layer.decrypt_chunks(&key, truncated, b"", 64).is_err());
+
+            let empty = layer.encrypt_chunks(&key, &[], b"", 64).unwrap();
+            assert_eq!(layer.decrypt_chunks(&key, &empty, b"", 64).unwrap(), Vec::<u8>::new());
+            assert!(layer.decrypt_chunks(&key, &[], b"", 64).is_err());
+        }
+    }
+
     #[test]
     fn test_fractal_layer() {
         let data = b"Test fractal layer".to_vec();
-        let layer = FractalLayer::new();
-        
-        let node = layer.generate(data.clone(), 12345, 3, 64).unwrap();
+        let layer = FractalLayer::with_params(3, 64);
+
+        let node = layer.generate(data.clone(), &Key256::insecure_from_u64(12345)).unwrap();
         let decrypted = node.decrypt().unwrap();
-        
+
         assert_eq!(data, decrypted);
     }
+
+    #[test]
+    fn test_fractal_layer_transforms_data() {
+        let data = b"Test fractal layer transformation over several chunks".to_vec();
+        let layer = FractalLayer::with_params(3, 8);
+
+        let seed = Key256::insecure_from_u64(12345);
+
+        let encrypted = layer.encrypt_with_seed(data.clone(), &seed).unwrap();
+        assert_eq!(encrypted.len(), data.len());
+        assert_ne!(encrypted, data);
+
+        let decrypted = layer.decrypt_with_seed(encrypted.clone(), &seed).unwrap();
+        assert_eq!(decrypted, data);
+
+        let wrong_seed = layer.decrypt_with_seed(encrypted, &seed.derive(b"wrong")).unwrap();
+        assert_ne!(wrong_seed, data);
+    }
+
+    #[test]
+    fn test_layers_through_trait() {
+        let data = b"Layers used as trait objects".to_vec();
+        let seed = Key256::insecure_from_u64(12345);
+        let ctx = LayerContext {
+            key: test_key(),
+            initial_seed: &seed,
+            associated_data: b"context",
+            parallel_chunk_size: None,
+        };
+
+        let layers: Vec<Arc<dyn Layer>> = vec![
+            Arc::new(FractalLayer::with_params(2, 4)),
+            Arc::new(FractalLayer::with_params(2, 4).shuffled()),
+            Arc::new(TranspositionLayer),
+            Arc::new(SymmetricLayer::Aes),
+            Arc::new(SymmetricLayer::ChaCha),
+            Arc::new(SymmetricLayer::ChaChaPoly),
+            Arc::new(SymmetricLayer::XChaChaPoly),
+        ];
+        for layer in layers {
+            let encrypted = layer.encrypt(&ctx, data.clone()).unwrap();
+            assert_ne!(encrypted, data, "{} left data unchanged", layer.id());
+            assert_eq!(layer.decrypt(&ctx, encrypted).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_layer_params() {
+        let params = FractalLayer::with_params(2, 128).params();
+        assert_eq!(params.iter().collect::<Vec<_>>(), vec![("depth", "2"), ("chunk", "128")]);
+
+        let rebuilt = FractalLayer::from_params(&params).unwrap();
+        assert_eq!(rebuilt.params(), params);
+
+        assert!(FractalLayer::from_params(&LayerParams::new()).is_err());
+        assert!(FractalLayer::from_params(&params.clone().with("depth", "x")).is_err());
+        assert!(FractalLayer::from_params(&params.clone().with("depth", 0)).is_err());
+
+        let shuffled = FractalLayer::with_params(2, 128).shuffled().params();
+        assert_eq!(shuffled.get("shuffle"), Some("true"));
+        assert_eq!(FractalLayer::from_params(&shuffled).unwrap().params(), shuffled);
+        assert!(FractalLayer::from_params(&params.with("shuffle", "maybe")).is_err());
+    }
+
+    #[test]
+    fn test_shuffled_chunks_and_transposition() {
+        let data: Vec<u8> = (0..100u8).collect();
+        let seed = Key256::insecure_from_u64(12345);
+
+        // Shuffling moves whole leaf chunks of the plain fractal output
+        let plain = FractalLayer::with_params(2, 10).encrypt_with_seed(data.clone(), &seed).unwrap();
+        let shuffled = FractalLayer::with_params(2, 10).shuffled().encrypt_with_seed(data.clone(), &seed).unwrap();
+        assert_ne!(shuffled, plain);
+        let mut plain_chunks: Vec<_> = plain.chunks(10).collect();
+        let mut shuffled_chunks: Vec<_> = shuffled.chunks(10).collect();
+        plain_chunks.sort();
+        shuffled_chunks.sort();
+        assert_eq!(plain_chunks, shuffled_chunks);
+
+        // Transposition keeps the byte values
+        let ctx = LayerContext {
+            key: test_key(),
+            initial_seed: &seed,
+            associated_data: b"",
+            parallel_chunk_size: None,
+        };
+        let mut transposed = TranspositionLayer.encrypt(&ctx, data.clone()).unwrap();
+        assert_ne!(transposed, data);
+        transposed.sort();
+        assert_eq!(transposed, data);
+        assert!(TranspositionLayer.encrypt(&ctx, Vec::new()).unwrap().is_empty());
+    }
 }
@@ -1,147 +1,253 @@
 mod config;
+mod envelope;
+mod keys;
 mod layer;
+mod registry;
+mod spec;
+mod stream;
 
 pub use config::LayerConfig;
-pub use layer::{Layer, SymmetricLayer, FractalLayer};
+pub use envelope::EnvelopeHeader;
+pub use keys::{LayerKey, KEY_ID_LEN, MESSAGE_NONCE_LEN};
+pub use layer::{Layer, LayerContext, LayerParams, SymmetricLayer, FractalLayer, TranspositionLayer};
+pub use registry::{LayerFactory, LayerRegistry};
+pub use stream::DEFAULT_SEGMENT_SIZE;
 
-use crate::{Result, EntropyBuilder};
-use rand::SeedableRng;
+use crate::{Result, FskcError, EntropyBuilder, Key256};
+use keys::MasterKey;
+use rand::{RngCore, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 use std::sync::{Arc, Mutex};
 
+/// Number of leading ciphertext bytes that key the self-zippering step
+const ZIPPER_HEAD_LEN: usize = 8;
+
 /// Manages layered encryption with configurable sequences and parameters
+///
+/// Every layer key is derived from the master secret, the layer index and a
+/// fresh per-message nonce. Only the key identifier and the nonce are written
+/// in front of the ciphertext.
 pub struct LayeredCrypto {
     entropy: Arc<Mutex<crate::entropy::CombinedEntropy>>,
+    master_key: MasterKey,
     config: LayerConfig,
+    registry: LayerRegistry,
+    segment_size: usize,
 }
 
 impl LayeredCrypto {
     /// Creates a new LayeredCrypto instance with default configuration
-    pub fn new(master_seed: u64) -> Self {
-        let rng = ChaCha20Rng::seed_from_u64(master_seed);
-        let entropy = EntropyBuilder::new()
-            .add_rng(rng, "Master RNG")
-            .build();
-
-        Self {
-            entropy,
-            config: LayerConfig::default(),
-        }
+    pub fn new(master_key: &Key256) -> Self {
+        Self::with_config(master_key, LayerConfig::default())
     }
 
     /// Creates a new LayeredCrypto instance with custom configuration
-    pub fn with_config(master_seed: u64, config: LayerConfig) -> Self {
-        let rng = ChaCha20Rng::seed_from_u64(master_seed);
+    pub fn with_config(master_key: &Key256, config: LayerConfig) -> Self {
         let entropy = EntropyBuilder::new()
-            .add_rng(rng, "Master RNG")
+            .add_rng(ChaCha20Rng::from_entropy(), "Nonce RNG")
             .build();
 
-        Self {
-            entropy,
-            config,
-        }
+        Self::with_entropy(master_key, entropy, config)
     }
 
     /// Creates a new LayeredCrypto instance with custom entropy sources
-    pub fn with_entropy(entropy: Arc<Mutex<crate::entropy::CombinedEntropy>>, config: LayerConfig) -> Self {
+    ///
+    /// The entropy sources are only used for per-message nonces; all key
+    /// material is derived from `master_key`.
+    pub fn with_entropy(
+        master_key: &Key256,
+        entropy: Arc<Mutex<crate::entropy::CombinedEntropy>>,
+        config: LayerConfig,
+    ) -> Self {
         Self {
             entropy,
+            master_key: MasterKey::new(master_key),
             config,
+            registry: LayerRegistry::default(),
+            segment_size: DEFAULT_SEGMENT_SIZE,
         }
     }
 
+    /// Returns the identifier of the master key written into ciphertexts
+    pub fn key_id(&self) -> [u8; KEY_ID_LEN] {
+        self.master_key.key_id()
+    }
+
+    /// Registers a custom layer so envelopes using it can be opened
+    pub fn register_layer(&mut self, id: &str, factory: LayerFactory) -> Result<()> {
+        self.registry.register(id, factory)
+    }
+
     /// Encrypts data using the configured layer sequence
-    pub fn encrypt(&mut self, data: &[u8], initial_seed: u64) -> Result<Vec<u8>> {
-        let mut current = data.to_vec();
+    ///
+    /// The output is a self-describing envelope: a header carrying the
+    /// layer configuration, key identifier and message nonce, followed by
+    /// the encrypted body. The header and `associated_data` are bound into
+    /// every authenticated layer; `associated_data` is not included in the
+    /// output and must be supplied again to decrypt.
+    pub fn encrypt(&mut self, data: &[u8], associated_data: &[u8], initial_seed: &Key256) -> Result<Vec<u8>> {
+        let message_nonce = self.fresh_nonce()?;
+        let header = EnvelopeHeader::new(
+            self.config.clone(),
+            self.master_key.key_id(),
+            message_nonce,
+        );
+
+        let mut output = header.encode()?;
+        let bound = bound_data(&output, associated_data);
+        output.extend(self.encrypt_body(&self.config, &message_nonce, data.to_vec(), &bound, initial_seed)?);
+        Ok(output)
+    }
 
+    /// Decrypts an envelope produced by [`LayeredCrypto::encrypt`]
+    ///
+    /// Equivalent to [`LayeredCrypto::open`]; the layer configuration is
+    /// taken from the envelope rather than from this instance.
+    pub fn decrypt(&mut self, data: &[u8], associated_data: &[u8], initial_seed: &Key256) -> Result<Vec<u8>> {
+        self.open(data, associated_data, initial_seed)
+    }
+
+    /// Opens an envelope using only the key material held by this instance
+    ///
+    /// The layer sequence and its parameters are read from the envelope
+    /// header, so ciphertexts remain readable after the configuration of
+    /// this instance has changed.
+    pub fn open(&mut self, data: &[u8], associated_data: &[u8], initial_seed: &Key256) -> Result<Vec<u8>> {
+        let (header, body) = EnvelopeHeader::decode_with(data, &self.registry)?;
+        self.check_key_id(&header)?;
+        if header.segment_size.is_some() {
+            return Err(FskcError::DecryptionError(
+                "Envelope is a segmented stream; use decrypt_stream".into()
+            ));
+        }
+
+        let bound = bound_data(&data[..data.len() - body.len()], associated_data);
+        self.decrypt_body(&header.config, &header.message_nonce, body.to_vec(), &bound, initial_seed)
+    }
+
+    /// Draws a fresh per-message nonce from the entropy pool
+    fn fresh_nonce(&self) -> Result<[u8; MESSAGE_NONCE_LEN]> {
+        let mut message_nonce = [0u8; MESSAGE_NONCE_LEN];
+        self.entropy.lock().unwrap().try_fill_bytes(&mut message_nonce)?;
+        Ok(message_nonce)
+    }
+
+    /// Rejects envelopes written under a different master key
+    fn check_key_id(&self, header: &EnvelopeHeader) -> Result<()> {
+        if header.key_id != self.master_key.key_id() {
+            return Err(FskcError::DecryptionError("Key identifier mismatch".into()));
+        }
+        Ok(())
+    }
+
+    /// Runs the layer sequence and zippering over a message body
+    fn encrypt_body(
+        &self,
+        config: &LayerConfig,
+        message_nonce: &[u8; MESSAGE_NONCE_LEN],
+        mut current: Vec<u8>,
+        associated_data: &[u8],
+        initial_seed: &Key256,
+    ) -> Result<Vec<u8>> {
         // Apply each layer in sequence
-        for layer in &self.config.sequence {
-            current = match layer {
-                Layer::Fractal(config) => {
-                    let node = config.generate(
-                        current,
-                        initial_seed,
-                        self.config.fractal_depth,
-                        self.config.chunk_size,
-                    )?;
-                    node.decrypt()?
-                },
-                Layer::Symmetric(algo) => {
-                    {
-                        let mut entropy = self.entropy.lock().unwrap();
-                        algo.encrypt(&mut *entropy, &current)?
-                    }
-                }
-            };
+        for (index, layer) in config.sequence.iter().enumerate() {
+            let ctx = self.layer_context(config, index, message_nonce, associated_data, initial_seed);
+            current = layer.encrypt(&ctx, current)?;
         }
 
-        // Apply self-zippering if configured
-        if self.config.use_zippering {
-            let zipper_seed = initial_seed ^ 
-                u64::from_le_bytes(current[..8].try_into().unwrap_or([0; 8]));
-            let final_node = FractalLayer::new().generate(
-                current,
-                zipper_seed,
-                self.config.fractal_depth,
-                self.config.chunk_size,
-            )?;
-            current = final_node.decrypt()?;
+        // Apply self-zippering if configured: the head of the ciphertext
+        // keys a fractal encryption of the remainder
+        if config.use_zippering && current.len() > ZIPPER_HEAD_LEN {
+            let tail = current.split_off(ZIPPER_HEAD_LEN);
+            let seed = self.zipper_seed(config, message_nonce, initial_seed, &current);
+            let zipper = FractalLayer::with_params(config.fractal_depth, config.chunk_size);
+            current.extend(zipper.encrypt_with_seed(tail, &seed)?);
         }
 
         Ok(current)
     }
 
-    /// Decrypts data using the configured layer sequence in reverse
-    pub fn decrypt(&mut self, data: &[u8], initial_seed: u64) -> Result<Vec<u8>> {
-        let mut current = data.to_vec();
-
+    /// Reverses [`LayeredCrypto::encrypt_body`]
+    fn decrypt_body(
+        &self,
+        config: &LayerConfig,
+        message_nonce: &[u8; MESSAGE_NONCE_LEN],
+        mut current: Vec<u8>,
+        associated_data: &[u8],
+        initial_seed: &Key256,
+    ) -> Result<Vec<u8>> {
         // Remove self-zippering if configured
-        if self.config.use_zippering {
-            let zipper_seed = initial_seed ^ 
-                u64::from_le_bytes(current[..8].try_into().unwrap_or([0; 8]));
-            let node = FractalLayer::new().generate(
-                current,
-                zipper_seed,
-                self.config.fractal_depth,
-                self.config.chunk_size,
-            )?;
-            current = node.decrypt()?;
+        if config.use_zippering && current.len() > ZIPPER_HEAD_LEN {
+            let tail = current.split_off(ZIPPER_HEAD_LEN);
+            let seed = self.zipper_seed(config, message_nonce, initial_seed, &current);
+            let zipper = FractalLayer::with_params(config.fractal_depth, config.chunk_size);
+            current.extend(zipper.decrypt_with_seed(tail, &seed)?);
         }
 
         // Apply layers in reverse
-        for layer in self.config.sequence.iter().rev() {
-            current = match layer {
-                Layer::Fractal(config) => {
-                    let node = config.generate(
-                        current,
-                        initial_seed,
-                        self.config.fractal_depth,
-                        self.config.chunk_size,
-                    )?;
-                    node.decrypt()?
-                },
-                Layer::Symmetric(algo) => {
-                    {
-                        let mut entropy = self.entropy.lock().unwrap();
-                        algo.decrypt(&mut *entropy, &current)?
-                    }
-                }
-            };
+        for (index, layer) in config.sequence.iter().enumerate().rev() {
+            let ctx = self.layer_context(config, index, message_nonce, associated_data, initial_seed);
+            current = layer.decrypt(&ctx, current)?;
         }
 
         Ok(current)
     }
+
+    /// Key material for the layer at `index` of a message
+    fn layer_context<'a>(
+        &self,
+        config: &LayerConfig,
+        index: usize,
+        message_nonce: &[u8; MESSAGE_NONCE_LEN],
+        associated_data: &'a [u8],
+        initial_seed: &'a Key256,
+    ) -> LayerContext<'a> {
+        LayerContext {
+            key: self.master_key.layer_key(index, message_nonce),
+            initial_seed,
+            associated_data,
+            parallel_chunk_size: config.parallel_chunk_size,
+        }
+    }
+
+    /// Seed for the zipper, derived from the head of the ciphertext
+    fn zipper_seed(
+        &self,
+        config: &LayerConfig,
+        message_nonce: &[u8; MESSAGE_NONCE_LEN],
+        initial_seed: &Key256,
+        head: &[u8],
+    ) -> Key256 {
+        let mut label = b"fskc-zipper".to_vec();
+        label.extend_from_slice(&head[..ZIPPER_HEAD_LEN]);
+        self.layer_context(config, config.sequence.len(), message_nonce, &[], initial_seed)
+            .seed()
+            .derive(&label)
+    }
+}
+
+/// Data bound into authenticated layers: the encoded header, which is
+/// self-delimiting, followed by the caller's associated data
+fn bound_data(raw_header: &[u8], associated_data: &[u8]) -> Vec<u8> {
+    let mut bound = Vec::with_capacity(raw_header.len() + associated_data.len());
+    bound.extend_from_slice(raw_header);
+    bound.extend_from_slice(associated_data);
+    bound
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn key(seed: u64) -> Key256 {
+        Key256::insecure_from_u64(seed)
+    }
+
     #[test]
     fn test_basic_sequence() {
         let data = b"Test data for layered encryption".to_vec();
-        let initial_seed = 12345;
-        let master_seed = 67890;
+        let initial_seed = key(12345);
+        let master_seed = key(67890);
 
         let config = LayerConfig::builder()
             .add_fractal()
@@ -150,11 +256,11 @@ mod tests {
             .add_fractal()
             .build();
 
-        let mut crypto = LayeredCrypto::with_config(master_seed, config.clone());
+        let mut crypto = LayeredCrypto::with_config(&master_seed, config.clone());
         
-        let encrypted = crypto.encrypt(&data, initial_seed).unwrap();
-        let mut crypto = LayeredCrypto::with_config(master_seed, config.clone());
-        let decrypted = crypto.decrypt(&encrypted, initial_seed).unwrap();
+        let encrypted = crypto.encrypt(&data, b"", &initial_seed).unwrap();
+        let mut crypto = LayeredCrypto::with_config(&master_seed, config.clone());
+        let decrypted = crypto.decrypt(&encrypted, b"", &initial_seed).unwrap();
         
         assert_eq!(data, decrypted);
     }
@@ -162,8 +268,8 @@ mod tests {
     #[test]
     fn test_with_zippering() {
         let data = b"Test data with self-zippering".to_vec();
-        let initial_seed = 12345;
-        let master_seed = 67890;
+        let initial_seed = key(12345);
+        let master_seed = key(67890);
 
         let config = LayerConfig::builder()
             .add_fractal()
@@ -171,12 +277,231 @@ mod tests {
             .enable_zippering()
             .build();
 
-        let mut crypto = LayeredCrypto::with_config(master_seed, config.clone());
+        let mut crypto = LayeredCrypto::with_config(&master_seed, config.clone());
         
-        let encrypted = crypto.encrypt(&data, initial_seed).unwrap();
-        let mut crypto = LayeredCrypto::with_config(master_seed, config.clone());
-        let decrypted = crypto.decrypt(&encrypted, initial_seed).unwrap();
+        let encrypted = crypto.encrypt(&data, b"", &initial_seed).unwrap();
+        let mut crypto = LayeredCrypto::with_config(&master_seed, config.clone());
+        let decrypted = crypto.decrypt(&encrypted, b"", &initial_seed).unwrap();
         
         assert_eq!(data, decrypted);
     }
+
+    #[test]
+    fn test_keys_not_shipped() {
+        let data = b"Test data for key derivation".to_vec();
+        let config = LayerConfig::builder()
+            .add_aes()
+            .add_chacha()
+            .build();
+
+        let mut crypto = LayeredCrypto::with_config(&key(67890), config.clone());
+        let encrypted = crypto.encrypt(&data, b"", &key(12345)).unwrap();
+
+        // Only the header and the AES-GCM tag are added
+        let (header, body) = EnvelopeHeader::decode(&encrypted).unwrap();
+        assert_eq!(body.len(), data.len() + 16);
+        assert_eq!(header.key_id, crypto.key_id());
+
+        // A different master secret cannot decrypt
+        let mut wrong = LayeredCrypto::with_config(&key(11111), config.clone());
+        assert!(wrong.decrypt(&encrypted, b"", &key(12345)).is_err());
+
+        // Even with a forged key identifier the layers fail to authenticate
+        let forged_header = EnvelopeHeader { key_id: wrong.key_id(), ..header };
+        let mut forged = forged_header.encode().unwrap();
+        forged.extend_from_slice(body);
+        assert!(wrong.decrypt(&forged, b"", &key(12345)).is_err());
+    }
+
+    #[test]
+    fn test_associated_data() {
+        let data = b"Authenticated but not encrypted context".to_vec();
+        for config in [
+            LayerConfig::builder().add_chacha_poly().build(),
+            LayerConfig::builder().add_fractal().add_xchacha_poly().enable_zippering().build(),
+            LayerConfig::builder().add_chacha().add_aes().build(),
+        ] {
+            let mut crypto = LayeredCrypto::with_config(&key(67890), config);
+            let encrypted = crypto.encrypt(&data, b"pairlet-key-7", &key(12345)).unwrap();
+
+            assert_eq!(crypto.decrypt(&encrypted, b"pairlet-key-7", &key(12345)).unwrap(), data);
+            assert!(crypto.decrypt(&encrypted, b"pairlet-key-8", &key(12345)).is_err());
+            assert!(crypto.decrypt(&encrypted, b"", &key(12345)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_header_is_authenticated() {
+        let data = b"Header fields the layers do not read".to_vec();
+        let config = LayerConfig::builder().add_chacha_poly().build();
+
+        let mut crypto = LayeredCrypto::with_config(&key(67890), config);
+        let encrypted = crypto.encrypt(&data, b"", &key(12345)).unwrap();
+
+        // The fractal depth is unused by this sequence but still bound
+        let (header, body) = EnvelopeHeader::decode(&encrypted).unwrap();
+        let mut config = header.config.clone();
+        config.fractal_depth += 1;
+        let mut tampered = EnvelopeHeader { config, ..header }.encode().unwrap();
+        tampered.extend_from_slice(body);
+        assert!(crypto.decrypt(&tampered, b"", &key(12345)).is_err());
+    }
+
+    #[test]
+    fn test_parallel_chunks() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let config = LayerConfig::builder()
+            .add_fractal()
+            .add_aes()
+            .add_chacha()
+            .add_xchacha_poly()
+            .chunk_size(256)
+            .parallel_chunks(1024)
+            .build();
+
+        let mut crypto = LayeredCrypto::with_config(&key(67890), config.clone());
+        let encrypted = crypto.encrypt(&data, b"ctx", &key(12345)).unwrap();
+
+        // Two authenticated layers each add a tag per 1 KiB chunk
+        let (header, body) = EnvelopeHeader::decode(&encrypted).unwrap();
+        assert_eq!(header.config, config);
+        assert!(body.len() > data.len() + 2 * 16 * 9);
+
+        let mut reader = LayeredCrypto::new(&key(67890));
+        assert_eq!(reader.open(&encrypted, b"ctx", &key(12345)).unwrap(), data);
+        assert!(reader.open(&encrypted, b"", &key(12345)).is_err());
+    }
+
+    #[test]
+    fn test_fresh_nonce_per_message() {
+        let data = b"Same plaintext twice".to_vec();
+        let mut crypto = LayeredCrypto::new(&key(67890));
+
+        let first = crypto.encrypt(&data, b"", &key(12345)).unwrap();
+        let second = crypto.encrypt(&data, b"", &key(12345)).unwrap();
+        assert_ne!(first, second);
+
+        assert_eq!(crypto.decrypt(&first, b"", &key(12345)).unwrap(), data);
+        assert_eq!(crypto.decrypt(&second, b"", &key(12345)).unwrap(), data);
+    }
+
+    #[test]
+    fn test_open_without_config() {
+        let data = b"Stored for months while configs drift".to_vec();
+        let config = LayerConfig::builder()
+            .add_chacha()
+            .add_fractal()
+            .add_aes()
+            .fractal_depth(2)
+            .chunk_size(16)
+            .enable_zippering()
+            .build();
+
+        let mut writer = LayeredCrypto::with_config(&key(67890), config.clone());
+        let encrypted = writer.encrypt(&data, b"", &key(12345)).unwrap();
+
+        let (header, _) = EnvelopeHeader::decode(&encrypted).unwrap();
+        assert_eq!(header.version, envelope::ENVELOPE_VERSION);
+        assert_eq!(header.config, config);
+
+        // The reader only shares the key material, not the configuration
+        let mut reader = LayeredCrypto::new(&key(67890));
+        assert_eq!(reader.open(&encrypted, b"", &key(12345)).unwrap(), data);
+    }
+
+    #[test]
+    fn test_fractal_layer_changes_data() {
+        let data = b"Fractal-only pipeline must not be the identity".to_vec();
+        let config = LayerConfig::builder()
+            .add_fractal()
+            .fractal_depth(3)
+            .chunk_size(8)
+            .enable_zippering()
+            .build();
+
+        let mut crypto = LayeredCrypto::with_config(&key(67890), config);
+        let encrypted = crypto.encrypt(&data, b"", &key(12345)).unwrap();
+
+        let (_, body) = EnvelopeHeader::decode(&encrypted).unwrap();
+        assert_eq!(body.len(), data.len());
+        assert_ne!(body, &data[..]);
+        assert_eq!(crypto.decrypt(&encrypted, b"", &key(12345)).unwrap(), data);
+
+        // The fractal layer depends on the initial seed as well
+        assert_ne!(crypto.decrypt(&encrypted, b"", &key(54321)).unwrap(), data);
+    }
+
+    #[test]
+    fn test_zippering_short_input() {
+        let config = LayerConfig::builder()
+            .add_fractal()
+            .enable_zippering()
+            .build();
+
+        let mut crypto = LayeredCrypto::with_config(&key(67890), config);
+        for len in 0..=ZIPPER_HEAD_LEN + 1 {
+            let data = vec![0x5a; len];
+            let encrypted = crypto.encrypt(&data, b"", &key(12345)).unwrap();
+            assert_eq!(crypto.decrypt(&encrypted, b"", &key(12345)).unwrap(), data);
+        }
+    }
+
+    /// Reverses the data under a keystream byte; only for exercising the registry
+    #[derive(Debug)]
+    struct ReverseLayer;
+
+    impl Layer for ReverseLayer {
+        fn id(&self) -> &str {
+            "reverse"
+        }
+
+        fn encrypt(&self, ctx: &LayerContext, mut data: Vec<u8>) -> Result<Vec<u8>> {
+            data.reverse();
+            data.iter_mut().for_each(|b| *b ^= ctx.key.key[0]);
+            Ok(data)
+        }
+
+        fn decrypt(&self, ctx: &LayerContext, mut data: Vec<u8>) -> Result<Vec<u8>> {
+            data.iter_mut().for_each(|b| *b ^= ctx.key.key[0]);
+            data.reverse();
+            Ok(data)
+        }
+    }
+
+    #[test]
+    fn test_transposition_stage() {
+        let config: LayerConfig = "fractal(depth=2,chunk=16,shuffle=true) > transpose > aes".parse().unwrap();
+        assert_eq!(config, LayerConfig::builder()
+            .add_layer(FractalLayer::with_params(2, 16).shuffled())
+            .add_transposition()
+            .add_aes()
+            .fractal_depth(2)
+            .chunk_size(16)
+            .build());
+
+        let mut crypto = LayeredCrypto::with_config(&key(67890), config);
+        let data = b"Shuffled chunks and transposed bytes under AES".to_vec();
+        let encrypted = crypto.encrypt(&data, b"", &key(12345)).unwrap();
+        assert_eq!(crypto.open(&encrypted, b"", &key(12345)).unwrap(), data);
+    }
+
+    #[test]
+    fn test_custom_layer() {
+        let data = b"Custom layers travel by identifier".to_vec();
+        let config = LayerConfig::builder()
+            .add_layer(ReverseLayer)
+            .add_aes()
+            .enable_zippering()
+            .build();
+
+        let mut writer = LayeredCrypto::with_config(&key(67890), config);
+        let encrypted = writer.encrypt(&data, b"", &key(12345)).unwrap();
+
+        // Readers must know how to rebuild the layer
+        let mut reader = LayeredCrypto::new(&key(67890));
+        assert!(reader.open(&encrypted, b"", &key(12345)).is_err());
+
+        reader.register_layer("reverse", |_| Ok(Arc::new(ReverseLayer))).unwrap();
+        assert_eq!(reader.open(&encrypted, b"", &key(12345)).unwrap(), data);
+    }
 }
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use crate::{Result, FskcError};
+use super::layer::{Layer, LayerParams, FractalLayer, SymmetricLayer, TranspositionLayer};
+
+/// Rebuilds a layer from the parameters stored in an envelope
+pub type LayerFactory = fn(&LayerParams) -> Result<Arc<dyn Layer>>;
+
+/// Maps layer identifiers to the factories that rebuild them
+#[derive(Clone)]
+pub struct LayerRegistry {
+    factories: HashMap<String, LayerFactory>,
+}
+
+impl Default for LayerRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.factories.insert(FractalLayer::ID.into(), FractalLayer::from_params);
+        registry.factories.insert(SymmetricLayer::AES_ID.into(), SymmetricLayer::aes_from_params);
+        registry.factories.insert(SymmetricLayer::CHACHA_ID.into(), SymmetricLayer::chacha_from_params);
+        registry.factories.insert(SymmetricLayer::CHACHA_POLY_ID.into(), SymmetricLayer::chacha_poly_from_params);
+        registry.factories.insert(SymmetricLayer::XCHACHA_POLY_ID.into(), SymmetricLayer::xchacha_poly_from_params);
+        registry.factories.insert(TranspositionLayer::ID.into(), TranspositionLayer::from_params);
+        registry
+    }
+}
+
+impl fmt::Debug for LayerRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids: Vec<_> = self.factories.keys().collect();
+        ids.sort();
+        f.debug_struct("LayerRegistry").field("layers", &ids).finish()
+    }
+}
+
+impl LayerRegistry {
+    /// Creates a registry containing the built-in layers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry without any layers
+    pub fn empty() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Registers a layer factory under `id`
+    ///
+    /// Identifiers are lowercase ASCII letters, digits, `-` and `_`, and
+    /// may not replace an identifier that is already registered.
+    pub fn register(&mut self, id: &str, factory: LayerFactory) -> Result<()> {
+        validate_id(id)?;
+        if self.factories.contains_key(id) {
+            return Err(FskcError::Custom(format!("Layer '{}' is already registered", id)));
+        }
+        self.factories.insert(id.into(), factory);
+        Ok(())
+    }
+
+    /// Returns true if a factory is registered under `id`
+    pub fn contains(&self, id: &str) -> bool {
+        self.factories.contains_key(id)
+    }
+
+    /// Rebuilds a layer from its identifier and parameters
+    pub fn build(&self, id: &str, params: &LayerParams) -> Result<Arc<dyn Layer>> {
+        let factory = self.factories.get(id).ok_or_else(|| {
+            FskcError::FormatError(format!("Unknown layer '{}'", id))
+        })?;
+
+        let layer = factory(params)?;
+        if layer.id() != id {
+            return Err(FskcError::FormatError(format!(
+                "Factory for '{}' built layer '{}'", id, layer.id()
+            )));
+        }
+        Ok(layer)
+    }
+}
+
+/// Checks that a layer identifier can be written into envelopes and specs
+pub(crate) fn validate_id(id: &str) -> Result<()> {
+    let valid = !id.is_empty()
+        && id.len() <= u8::MAX as usize
+        && id.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(FskcError::FormatError(format!("Invalid layer identifier '{}'", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_layers() {
+        let registry = LayerRegistry::new();
+        for id in ["fractal", "aes", "chacha", "chacha20poly1305", "xchacha20poly1305", "transpose"] {
+            assert!(registry.contains(id));
+        }
+
+        let params = LayerParams::new().with("depth", 2).with("chunk", 16);
+        let layer = registry.build("fractal", &params).unwrap();
+        assert_eq!(layer.id(), "fractal");
+        assert_eq!(layer.params(), params);
+
+        assert!(registry.build("unknown", &LayerParams::new()).is_err());
+    }
+
+    #[test]
+    fn test_register_validation() {
+        let mut registry = LayerRegistry::new();
+        assert!(registry.register("aes", SymmetricLayer::aes_from_params).is_err());
+        assert!(registry.register("", SymmetricLayer::aes_from_params).is_err());
+        assert!(registry.register("Bad Id", SymmetricLayer::aes_from_params).is_err());
+
+        // A factory must build the layer it is registered for
+        registry.register("aes-alias", SymmetricLayer::aes_from_params).unwrap();
+        assert!(registry.build("aes-alias", &LayerParams::new()).is_err());
+    }
+}
@@ -0,0 +1,557 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use crate::{Result, FskcError};
+use super::config::LayerConfig;
+use super::layer::{Layer, LayerParams, FractalLayer};
+use super::registry::{self, LayerRegistry};
+
+/// Fractal depth and chunk size used when a spec names no fractal layer
+const DEFAULT_FRACTAL: (usize, usize) = (3, 64);
+
+impl LayerConfig {
+    /// Parses a textual spec, rebuilding layers from `registry`
+    ///
+    /// A spec lists layers separated by `>` followed by `+` options, e.g.
+    /// `fractal(depth=3,chunk=64) > aes > chacha +zipper`. A `fractal`
+    /// layer without parameters uses the config-wide depth and chunk size.
+    /// The options are:
+    ///
+    /// - `+zipper` enables self-zippering
+    /// - `+parallel(chunk=N)` enables chunk-parallel symmetric layers
+    /// - `+fractal(depth=D,chunk=C)` sets the config-wide fractal
+    ///   parameters, which otherwise come from the first fractal layer
+    ///
+    /// Values that are not plain words are written as double-quoted
+    /// strings with `\"` and `\\` escapes.
+    pub fn parse_with(spec: &str, registry: &LayerRegistry) -> Result<Self> {
+        Parser::new(spec)?.parse(registry)
+    }
+}
+
+impl FromStr for LayerConfig {
+    type Err = FskcError;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        Self::parse_with(spec, &LayerRegistry::default())
+    }
+}
+
+impl fmt::Display for LayerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, layer) in self.sequence.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" > ")?;
+            }
+            f.write_str(layer.id())?;
+            write_params(f, &layer.params())?;
+        }
+
+        if self.use_zippering {
+            f.write_str(" +zipper")?;
+        }
+        if let Some(chunk_size) = self.parallel_chunk_size {
+            write!(f, " +parallel(chunk={})", chunk_size)?;
+        }
+        if Some((self.fractal_depth, self.chunk_size)) != inferred_fractal(&self.sequence) {
+            write!(f, " +fractal(depth={},chunk={})", self.fractal_depth, self.chunk_size)?;
+        }
+        Ok(())
+    }
+}
+
+/// Config-wide fractal parameters a parser infers from the layers alone
+fn inferred_fractal(sequence: &[Arc<dyn Layer>]) -> Option<(usize, usize)> {
+    match sequence.iter().find(|layer| layer.id() == FractalLayer::ID) {
+        Some(layer) => {
+            let params = layer.params();
+            Some((params.parse("depth").ok()?, params.parse("chunk").ok()?))
+        }
+        None => Some(DEFAULT_FRACTAL),
+    }
+}
+
+fn write_params(f: &mut fmt::Formatter<'_>, params: &LayerParams) -> fmt::Result {
+    if params.is_empty() {
+        return Ok(());
+    }
+
+    f.write_str("(")?;
+    for (i, (key, value)) in params.iter().enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        write_word(f, key)?;
+        f.write_str("=")?;
+        write_word(f, value)?;
+    }
+    f.write_str(")")
+}
+
+/// Writes a bare word, or a quoted string if it contains other characters
+fn write_word(f: &mut fmt::Formatter<'_>, word: &str) -> fmt::Result {
+    if !word.is_empty() && word.chars().all(is_word_char) {
+        return f.write_str(word);
+    }
+
+    f.write_str("\"")?;
+    for c in word.chars() {
+        if c == '"' || c == '\\' {
+            f.write_str("\\")?;
+        }
+        write!(f, "{}", c)?;
+    }
+    f.write_str("\"")
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '/')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Word(String),
+    Then,
+    Plus,
+    Open,
+    Close,
+    Comma,
+    Equals,
+    End,
+}
+
+/// A token and the byte range it covers
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Kind,
+    start: usize,
+    end: usize,
+}
+
+/// A layer as written, resolved once the fractal parameters are known
+struct PendingLayer {
+    id: String,
+    params: LayerParams,
+    token: Token,
+}
+
+struct Parser<'a> {
+    spec: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(spec: &'a str) -> Result<Self> {
+        Ok(Self {
+            spec,
+            tokens: tokenize(spec)?,
+            pos: 0,
+        })
+    }
+
+    fn parse(mut self, registry: &LayerRegistry) -> Result<LayerConfig> {
+        let mut pending = vec![self.layer()?];
+        while self.peek().kind == Kind::Then {
+            self.next();
+            pending.push(self.layer()?);
+        }
+
+        let mut use_zippering = false;
+        let mut parallel_chunk_size = None;
+        let mut fractal = None;
+        while self.peek().kind == Kind::Plus {
+            self.next();
+            let token = self.next();
+            let Kind::Word(name) = &token.kind else {
+                return Err(self.error(&token, "expected an option name"));
+            };
+            let params = self.params()?;
+
+            match name.as_str() {
+                "zipper" if !use_zippering => {
+                    self.no_params(&token, &params)?;
+                    use_zippering = true;
+                }
+                "parallel" if parallel_chunk_size.is_none() => {
+                    let chunk = self.size_param(&token, &params, "chunk")?;
+                    self.only_params(&token, &params, &["chunk"])?;
+                    parallel_chunk_size = Some(chunk);
+                }
+                "fractal" if fractal.is_none() => {
+                    let depth = self.size_param(&token, &params, "depth")?;
+                    let chunk = self.size_param(&token, &params, "chunk")?;
+                    self.only_params(&token, &params, &["depth", "chunk"])?;
+                    fractal = Some((depth, chunk));
+                }
+                "zipper" | "parallel" | "fractal" => {
+                    return Err(self.error(&token, "option given more than once"));
+                }
+                _ => return Err(self.error(&token, "unknown option")),
+            }
+        }
+
+        let token = self.next();
+        if token.kind != Kind::End {
+            return Err(self.error(&token, "expected '>', '+' or end of spec"));
+        }
+
+        // Without an explicit option the first fractal layer sets the
+        // config-wide parameters
+        let (fractal_depth, chunk_size) = match fractal {
+            Some(fractal) => fractal,
+            None => match pending.iter().find(|layer| layer.id == FractalLayer::ID) {
+                Some(layer) => (
+                    self.size_param_or(&layer.token, &layer.params, "depth", DEFAULT_FRACTAL.0)?,
+                    self.size_param_or(&layer.token, &layer.params, "chunk", DEFAULT_FRACTAL.1)?,
+                ),
+                None => DEFAULT_FRACTAL,
+            },
+        };
+
+        let mut sequence = Vec::with_capacity(pending.len());
+        for mut layer in pending {
+            if layer.id == FractalLayer::ID {
+                if layer.params.get("depth").is_none() {
+                    layer.params.insert("depth", fractal_depth);
+                }
+                if layer.params.get("chunk").is_none() {
+                    layer.params.insert("chunk", chunk_size);
+                }
+            }
+
+            let built = registry.build(&layer.id, &layer.params).map_err(|e| {
+                let message = match e {
+                    FskcError::FormatError(message) => message,
+                    other => other.to_string(),
+                };
+                self.error(&layer.token, &message)
+            })?;
+            sequence.push(built);
+        }
+
+        Ok(LayerConfig {
+            sequence,
+            fractal_depth,
+            chunk_size,
+            use_zippering,
+            parallel_chunk_size,
+        })
+    }
+
+    fn layer(&mut self) -> Result<PendingLayer> {
+        let token = self.next();
+        let Kind::Word(id) = &token.kind else {
+            return Err(self.error(&token, "expected a layer name"));
+        };
+        if registry::validate_id(id).is_err() {
+            return Err(self.error(&token, "invalid layer name"));
+        }
+
+        Ok(PendingLayer {
+            id: id.clone(),
+            params: self.params()?,
+            token,
+        })
+    }
+
+    /// Parses an optional parenthesized `key=value` list
+    fn params(&mut self) -> Result<LayerParams> {
+        let mut params = LayerParams::new();
+        if self.peek().kind != Kind::Open {
+            return Ok(params);
+        }
+        self.next();
+
+        loop {
+            let key = self.next();
+            let Kind::Word(name) = &key.kind else {
+                return Err(self.error(&key, "expected a parameter name"));
+            };
+            if params.get(name).is_some() {
+                return Err(self.error(&key, "parameter given more than once"));
+            }
+
+            let equals = self.next();
+            if equals.kind != Kind::Equals {
+                return Err(self.error(&equals, "expected '='"));
+            }
+
+            let value = self.next();
+            let Kind::Word(value) = value.kind else {
+                return Err(self.error(&value, "expected a parameter value"));
+            };
+            params.insert(name.clone(), value);
+
+            let token = self.next();
+            match token.kind {
+                Kind::Comma => continue,
+                Kind::Close => return Ok(params),
+                _ => return Err(self.error(&token, "expected ',' or ')'")),
+            }
+        }
+    }
+
+    fn size_param(&self, token: &Token, params: &LayerParams, key: &str) -> Result<usize> {
+        match params.get(key) {
+            Some(_) => self.size_param_or(token, params, key, 0),
+            None => Err(self.error(token, &format!("missing parameter '{}'", key))),
+        }
+    }
+
+    fn size_param_or(
+        &self,
+        token: &Token,
+        params: &LayerParams,
+        key: &str,
+        default: usize,
+    ) -> Result<usize> {
+        let Some(value) = params.get(key) else {
+            return Ok(default);
+        };
+        match value.parse() {
+            Ok(size) if size > 0 => Ok(size),
+            _ => Err(self.error(token, &format!("'{}' must be a positive integer", key))),
+        }
+    }
+
+    fn no_params(&self, token: &Token, params: &LayerParams) -> Result<()> {
+        self.only_params(token, params, &[])
+    }
+
+    fn only_params(&self, token: &Token, params: &LayerParams, allowed: &[&str]) -> Result<()> {
+        match params.iter().find(|(key, _)| !allowed.contains(key)) {
+            Some((key, _)) => Err(self.error(token, &format!("unexpected parameter '{}'", key))),
+            None => Ok(()),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token.kind != Kind::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, token: &Token, message: &str) -> FskcError {
+        spec_error(self.spec, token.start, token.end, message)
+    }
+}
+
+fn spec_error(spec: &str, start: usize, end: usize, message: &str) -> FskcError {
+    FskcError::SpecError {
+        column: spec[..start].chars().count() + 1,
+        token: if start == end {
+            "end of spec".into()
+        } else {
+            spec[start..end].into()
+        },
+        message: message.into(),
+    }
+}
+
+fn tokenize(spec: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = spec.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let kind = match c {
+            '>' => Kind::Then,
+            '+' => Kind::Plus,
+            '(' => Kind::Open,
+            ')' => Kind::Close,
+            ',' => Kind::Comma,
+            '=' => Kind::Equals,
+            '"' => {
+                chars.next();
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c @ ('"' | '\\'))) => word.push(c),
+                            Some((i, c)) => {
+                                return Err(spec_error(spec, i, i + c.len_utf8(), "unknown escape"));
+                            }
+                            None => {
+                                return Err(spec_error(spec, start, spec.len(), "unterminated string"));
+                            }
+                        },
+                        Some((_, c)) => word.push(c),
+                        None => {
+                            return Err(spec_error(spec, start, spec.len(), "unterminated string"));
+                        }
+                    }
+                }
+                let end = chars.peek().map_or(spec.len(), |&(i, _)| i);
+                tokens.push(Token { kind: Kind::Word(word), start, end });
+                continue;
+            }
+            c if is_word_char(c) => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !is_word_char(c) {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                tokens.push(Token {
+                    kind: Kind::Word(spec[start..end].into()),
+                    start,
+                    end,
+                });
+                continue;
+            }
+            c => return Err(spec_error(spec, start, start + c.len_utf8(), "unexpected character")),
+        };
+
+        chars.next();
+        tokens.push(Token { kind, start, end: start + c.len_utf8() });
+    }
+
+    tokens.push(Token { kind: Kind::End, start: spec.len(), end: spec.len() });
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layered::{LayerContext, SymmetricLayer};
+
+    fn spec_error_at(spec: &str) -> (usize, String) {
+        match spec.parse::<LayerConfig>() {
+            Err(FskcError::SpecError { column, token, .. }) => (column, token),
+            other => panic!("expected a spec error for {:?}, got {:?}", spec, other),
+        }
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let config: LayerConfig = "fractal(depth=3,chunk=64) > aes > chacha +zipper".parse().unwrap();
+        let expected = LayerConfig::builder()
+            .add_fractal()
+            .add_aes()
+            .add_chacha()
+            .fractal_depth(3)
+            .chunk_size(64)
+            .enable_zippering()
+            .build();
+        assert_eq!(config, expected);
+
+        // Bare fractal layers pick up the config-wide parameters
+        let config: LayerConfig = "aes>fractal +fractal(depth=2,chunk=16) +parallel(chunk=4096)"
+            .parse()
+            .unwrap();
+        assert_eq!(config.layers()[1].params().get("chunk"), Some("16"));
+        assert_eq!(config.parallel_chunk_size(), Some(4096));
+    }
+
+    #[test]
+    fn test_spec_roundtrip() {
+        let configs = [
+            LayerConfig::default(),
+            LayerConfig::builder()
+                .add_chacha_poly()
+                .add_fractal()
+                .add_xchacha_poly()
+                .fractal_depth(2)
+                .chunk_size(16)
+                .enable_zippering()
+                .parallel_chunks(1024)
+                .build(),
+            LayerConfig::builder().add_aes().chunk_size(8).enable_zippering().build(),
+            LayerConfig::builder()
+                .add_layer(FractalLayer::with_params(5, 16))
+                .add_fractal()
+                .build(),
+        ];
+
+        for config in configs {
+            let text = config.to_string();
+            let parsed: LayerConfig = text.parse().unwrap();
+            assert_eq!(parsed, config, "{}", text);
+            assert_eq!(parsed.to_string(), text);
+        }
+
+        assert_eq!(
+            LayerConfig::default().to_string(),
+            "fractal(depth=3,chunk=64) > aes"
+        );
+    }
+
+    #[test]
+    fn test_spec_errors_point_to_token() {
+        assert_eq!(spec_error_at("aes > rot13"), (7, "rot13".into()));
+        assert_eq!(spec_error_at("aes > > chacha"), (7, ">".into()));
+        assert_eq!(spec_error_at("fractal(depth=x) > aes"), (1, "fractal".into()));
+        assert_eq!(spec_error_at("fractal(depth=2 chunk=3)"), (17, "chunk".into()));
+        assert_eq!(spec_error_at("aes +zip"), (6, "zip".into()));
+        assert_eq!(spec_error_at("aes +zipper +zipper"), (14, "zipper".into()));
+        assert_eq!(spec_error_at("aes > chacha; aes"), (13, ";".into()));
+        assert_eq!(spec_error_at("aes >"), (6, "end of spec".into()));
+        assert_eq!(spec_error_at("Aes"), (1, "Aes".into()));
+        assert_eq!(spec_error_at("aes +parallel(chunk=0)"), (6, "parallel".into()));
+        assert_eq!(spec_error_at("custom(note=\"open"), (13, "\"open".into()));
+
+        let err = "aes \"abc\\".parse::<LayerConfig>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid layer spec at column 5 near '\"abc\\': unterminated string"
+        );
+
+        let err = "aes > rot13".parse::<LayerConfig>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid layer spec at column 7 near 'rot13': Unknown layer 'rot13'"
+        );
+    }
+
+    #[derive(Debug)]
+    struct Tagged(String);
+
+    impl Layer for Tagged {
+        fn id(&self) -> &str {
+            "tagged"
+        }
+
+        fn params(&self) -> LayerParams {
+            LayerParams::new().with("tag", &self.0)
+        }
+
+        fn encrypt(&self, _ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+            Ok(data)
+        }
+
+        fn decrypt(&self, _ctx: &LayerContext, data: Vec<u8>) -> Result<Vec<u8>> {
+            Ok(data)
+        }
+    }
+
+    #[test]
+    fn test_spec_with_custom_layer() {
+        let mut registry = LayerRegistry::new();
+        registry
+            .register("tagged", |params| Ok(Arc::new(Tagged(params.parse("tag")?))))
+            .unwrap();
+
+        let config = LayerConfig::builder()
+            .add_layer(Tagged("needs \"quotes\", and \\ (escapes)".into()))
+            .add_shared_layer(Arc::new(SymmetricLayer::Aes))
+            .build();
+
+        let text = config.to_string();
+        assert!(text.parse::<LayerConfig>().is_err());
+        assert_eq!(LayerConfig::parse_with(&text, &registry).unwrap(), config);
+    }
+}
@@ -0,0 +1,471 @@
+use std::io::{ErrorKind, Read, Write};
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use crate::{Result, FskcError, Key256};
+use super::{LayeredCrypto, LayerConfig, EnvelopeHeader, MESSAGE_NONCE_LEN};
+
+/// Default number of plaintext bytes per stream segment
+pub const DEFAULT_SEGMENT_SIZE: usize = 64 * 1024;
+
+/// Authentication tag added by each AES-GCM pass
+const TAG_LEN: usize = 16;
+
+/// Bytes of the message nonce kept as the per-segment nonce prefix
+const NONCE_PREFIX_LEN: usize = MESSAGE_NONCE_LEN - 5;
+
+/// Frame length bit marking the final segment
+const LAST_SEGMENT: u32 = 1 << 31;
+
+impl LayeredCrypto {
+    /// Sets the plaintext segment size used by [`LayeredCrypto::encrypt_stream`]
+    ///
+    /// The size is rejected if a sealed segment could outgrow the frame
+    /// length, counting the overhead of every layer of the configuration.
+    pub fn set_segment_size(&mut self, segment_size: usize) -> Result<()> {
+        if !valid_segment_size(&self.config, segment_size) {
+            return Err(FskcError::InvalidDataSize(segment_size));
+        }
+        self.segment_size = segment_size;
+        Ok(())
+    }
+
+    /// Encrypts everything read from `reader` into a segmented stream
+    ///
+    /// The plaintext is split into segments of the configured size, each
+    /// run through the layer sequence under its own nonce and sealed with
+    /// AES-GCM. The segment nonce carries a counter and a final-segment
+    /// flag, so reordered, dropped or truncated segments fail to open.
+    /// Memory use is bounded by the segment size.
+    ///
+    /// Returns the number of plaintext bytes consumed.
+    pub fn encrypt_stream<R: Read, W: Write>(
+        &mut self,
+        mut reader: R,
+        mut writer: W,
+        initial_seed: &Key256,
+    ) -> Result<u64> {
+        let mut header = EnvelopeHeader::new(
+            self.config.clone(),
+            self.master_key.key_id(),
+            self.fresh_nonce()?,
+        );
+        header.segment_size = Some(self.segment_size);
+        let raw_header = header.encode()?;
+        writer.write_all(&raw_header)?;
+
+        let mut current = vec![0u8; self.segment_size];
+        let mut next = vec![0u8; self.segment_size];
+        let mut current_len = read_full(&mut reader, &mut current)?;
+        let mut counter = 0u32;
+        let mut total = 0u64;
+
+        loop {
+            // Look ahead one segment so the final one can be flagged
+            let next_len = if current_len == current.len() {
+                read_full(&mut reader, &mut next)?
+            } else {
+                0
+            };
+            let last = next_len == 0;
+
+            let segment_nonce = segment_nonce(&header.message_nonce, counter, last);
+            let body = self.encrypt_body(
+                &header.config,
+                &segment_nonce,
+                current[..current_len].to_vec(),
+                &[],
+                initial_seed,
+            )?;
+            let sealed = self.seal_segment(&segment_nonce, &raw_header, &body)?;
+
+            writer.write_all(&frame_len(sealed.len(), last)?.to_le_bytes())?;
+            writer.write_all(&sealed)?;
+            total += current_len as u64;
+
+            if last {
+                break;
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            current_len = next_len;
+            counter = counter.checked_add(1).ok_or_else(|| {
+                FskcError::EncryptionError("Too many stream segments".into())
+            })?;
+        }
+
+        writer.flush()?;
+        Ok(total)
+    }
+
+    /// Decrypts a stream written by [`LayeredCrypto::encrypt_stream`]
+    ///
+    /// Plaintext is written segment by segment as each one authenticates.
+    /// An error is returned if segments are missing, reordered or modified,
+    /// or if the stream ends before its final segment.
+    ///
+    /// Returns the number of plaintext bytes written.
+    pub fn decrypt_stream<R: Read, W: Write>(
+        &mut self,
+        mut reader: R,
+        mut writer: W,
+        initial_seed: &Key256,
+    ) -> Result<u64> {
+        let (header, raw_header) = EnvelopeHeader::read_from_with(&mut reader, &self.registry)?;
+        self.check_key_id(&header)?;
+        let segment_size = header.segment_size.ok_or_else(|| {
+            FskcError::DecryptionError("Envelope is not a segmented stream".into())
+        })?;
+
+        let max_frame = max_sealed_len(&header.config, segment_size);
+        let mut frame = Vec::new();
+        let mut counter = 0u32;
+        let mut total = 0u64;
+
+        loop {
+            let mut len_bytes = [0u8; 4];
+            read_segment_bytes(&mut reader, &mut len_bytes)?;
+            let frame_len = u32::from_le_bytes(len_bytes);
+            let last = frame_len & LAST_SEGMENT != 0;
+            let sealed_len = (frame_len & !LAST_SEGMENT) as usize;
+            if sealed_len > max_frame {
+                return Err(FskcError::FormatError(format!(
+                    "Segment of {} bytes exceeds limit of {}", sealed_len, max_frame
+                )));
+            }
+
+            read_frame(&mut reader, &mut frame, sealed_len)?;
+
+            let segment_nonce = segment_nonce(&header.message_nonce, counter, last);
+            let body = self.open_segment(&segment_nonce, &raw_header, &frame)?;
+            let plaintext = self.decrypt_body(&header.config, &segment_nonce, body, &[], initial_seed)?;
+
+            let expected_full = !last && plaintext.len() != segment_size;
+            if expected_full || plaintext.len() > segment_size {
+                return Err(FskcError::DecryptionError("Invalid segment length".into()));
+            }
+
+            writer.write_all(&plaintext)?;
+            total += plaintext.len() as u64;
+
+            if last {
+                break;
+            }
+
+            counter = counter.checked_add(1).ok_or_else(|| {
+                FskcError::DecryptionError("Too many stream segments".into())
+            })?;
+        }
+
+        let mut trailing = [0u8; 1];
+        if reader.read(&mut trailing)? != 0 {
+            return Err(FskcError::DecryptionError("Data after final segment".into()));
+        }
+
+        writer.flush()?;
+        Ok(total)
+    }
+
+    fn seal_segment(
+        &self,
+        segment_nonce: &[u8; MESSAGE_NONCE_LEN],
+        raw_header: &[u8],
+        body: &[u8],
+    ) -> Result<Vec<u8>> {
+        let key = self.master_key.segment_key(segment_nonce);
+        let cipher = Aes256Gcm::new_from_slice(&key.key)
+            .map_err(|e| FskcError::EncryptionError(e.to_string()))?;
+
+        cipher
+            .encrypt(Nonce::from_slice(&key.nonce), Payload { msg: body, aad: raw_header })
+            .map_err(|e| FskcError::EncryptionError(e.to_string()))
+    }
+
+    fn open_segment(
+        &self,
+        segment_nonce: &[u8; MESSAGE_NONCE_LEN],
+        raw_header: &[u8],
+        sealed: &[u8],
+    ) -> Result<Vec<u8>> {
+        let key = self.master_key.segment_key(segment_nonce);
+        let cipher = Aes256Gcm::new_from_slice(&key.key)
+            .map_err(|e| FskcError::DecryptionError(e.to_string()))?;
+
+        cipher
+            .decrypt(Nonce::from_slice(&key.nonce), Payload { msg: sealed, aad: raw_header })
+            .map_err(|_| FskcError::DecryptionError("Segment authentication failed".into()))
+    }
+}
+
+/// Returns true if every segment of `segment_size` bytes can be framed
+/// once sealed under `config`
+pub(super) fn valid_segment_size(config: &LayerConfig, segment_size: usize) -> bool {
+    segment_size > 0
+        && segment_size < LAST_SEGMENT as usize / 2
+        && max_sealed_len(config, segment_size) < LAST_SEGMENT as usize
+}
+
+/// Largest sealed segment `config` can produce from `segment_size` bytes
+///
+/// Every layer may add at most one tag, plus the segment seal.
+/// Chunk-parallel layers add their overhead once per chunk.
+fn max_sealed_len(config: &LayerConfig, segment_size: usize) -> usize {
+    let chunk_size = config.parallel_chunk_size;
+    config
+        .sequence
+        .iter()
+        .fold(segment_size, |limit, layer| {
+            let chunks = chunk_size.map_or(1, |size| limit.div_ceil(size).max(1));
+            limit.saturating_add(layer.overhead().saturating_mul(chunks))
+        })
+        .saturating_add(TAG_LEN)
+}
+
+/// Builds the nonce of one segment: message nonce prefix, counter, final flag
+fn segment_nonce(
+    message_nonce: &[u8; MESSAGE_NONCE_LEN],
+    counter: u32,
+    last: bool,
+) -> [u8; MESSAGE_NONCE_LEN] {
+    let mut nonce = [0u8; MESSAGE_NONCE_LEN];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(&message_nonce[..NONCE_PREFIX_LEN]);
+    nonce[NONCE_PREFIX_LEN..NONCE_PREFIX_LEN + 4].copy_from_slice(&counter.to_be_bytes());
+    nonce[MESSAGE_NONCE_LEN - 1] = last as u8;
+    nonce
+}
+
+/// Encodes the length word of a frame, rejecting lengths that would
+/// collide with the final-segment bit
+fn frame_len(sealed_len: usize, last: bool) -> Result<u32> {
+    let len = u32::try_from(sealed_len)
+        .ok()
+        .filter(|&len| len < LAST_SEGMENT)
+        .ok_or_else(|| {
+            FskcError::FormatError(format!("Segment of {} bytes is too large to frame", sealed_len))
+        })?;
+    Ok(if last { len | LAST_SEGMENT } else { len })
+}
+
+/// Reads until `buf` is full or the reader is exhausted
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+/// Reads a frame of `len` bytes, growing `frame` only as the bytes arrive
+fn read_frame<R: Read>(reader: &mut R, frame: &mut Vec<u8>, len: usize) -> Result<()> {
+    frame.clear();
+    reader.by_ref().take(len as u64).read_to_end(frame)?;
+    if frame.len() != len {
+        return Err(FskcError::DecryptionError("Stream truncated".into()));
+    }
+    Ok(())
+}
+
+fn read_segment_bytes<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => FskcError::DecryptionError("Stream truncated".into()),
+        _ => FskcError::IoError(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(seed: u64) -> Key256 {
+        Key256::insecure_from_u64(seed)
+    }
+    use crate::LayerConfig;
+
+    const SEGMENT: usize = 64;
+
+    fn stream_crypto() -> LayeredCrypto {
+        let config = LayerConfig::builder()
+            .add_fractal()
+            .add_chacha()
+            .add_aes()
+            .chunk_size(16)
+            .enable_zippering()
+            .build();
+        let mut crypto = LayeredCrypto::with_config(&key(67890), config);
+        crypto.set_segment_size(SEGMENT).unwrap();
+        crypto
+    }
+
+    fn encrypt(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        stream_crypto().encrypt_stream(data, &mut out, &key(12345)).unwrap();
+        out
+    }
+
+    fn decrypt(stream: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        stream_crypto().decrypt_stream(stream, &mut out, &key(12345))?;
+        Ok(out)
+    }
+
+    /// Splits a stream into its header and raw frames
+    fn split_frames(stream: &[u8]) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let mut reader = stream;
+        let (_, header) = EnvelopeHeader::read_from(&mut reader).unwrap();
+        let mut frames = Vec::new();
+        while !reader.is_empty() {
+            let len = u32::from_le_bytes(reader[..4].try_into().unwrap());
+            let end = 4 + (len & !LAST_SEGMENT) as usize;
+            frames.push(reader[..end].to_vec());
+            reader = &reader[end..];
+        }
+        (header, frames)
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        for len in [0, 1, SEGMENT - 1, SEGMENT, SEGMENT + 1, 3 * SEGMENT + 5] {
+            let data: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+            let stream = encrypt(&data);
+
+            let (_, frames) = split_frames(&stream);
+            assert_eq!(frames.len(), len.max(1).div_ceil(SEGMENT));
+            assert_eq!(decrypt(&stream).unwrap(), data, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn test_stream_with_parallel_chunks() {
+        let config = LayerConfig::builder()
+            .add_chacha_poly()
+            .add_aes()
+            .parallel_chunks(16)
+            .build();
+        let data: Vec<u8> = (0..5 * SEGMENT + 3).map(|i| i as u8).collect();
+
+        let mut crypto = LayeredCrypto::with_config(&key(67890), config);
+        crypto.set_segment_size(SEGMENT).unwrap();
+        let mut stream = Vec::new();
+        crypto.encrypt_stream(&data[..], &mut stream, &key(12345)).unwrap();
+
+        let mut out = Vec::new();
+        crypto.decrypt_stream(&stream[..], &mut out, &key(12345)).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_frame_len_bounds() {
+        assert_eq!(frame_len(16, false).unwrap(), 16);
+        assert_eq!(frame_len(16, true).unwrap(), 16 | LAST_SEGMENT);
+        assert_eq!(frame_len(LAST_SEGMENT as usize - 1, false).unwrap(), LAST_SEGMENT - 1);
+        assert!(matches!(frame_len(LAST_SEGMENT as usize, false), Err(FskcError::FormatError(_))));
+        assert!(frame_len(usize::MAX, true).is_err());
+    }
+
+    #[test]
+    fn test_segment_size_bounded_by_overhead() {
+        let limit = LAST_SEGMENT as usize / 2 - 1;
+        let mut plain = LayeredCrypto::with_config(&key(67890), LayerConfig::builder().add_chacha_poly().build());
+        assert!(plain.set_segment_size(limit).is_ok());
+
+        // A tag per 16-byte chunk doubles the segment past the frame length
+        let config = LayerConfig::builder().add_chacha_poly().parallel_chunks(16).build();
+        let mut chunked = LayeredCrypto::with_config(&key(67890), config);
+        assert!(matches!(chunked.set_segment_size(limit), Err(FskcError::InvalidDataSize(_))));
+        assert!(chunked.set_segment_size(limit / 2).is_ok());
+    }
+
+    #[test]
+    fn test_stream_rejects_forged_sizes() {
+        let crypto = stream_crypto();
+        let header = |segment_size| {
+            let mut header = EnvelopeHeader::new(crypto.config.clone(), crypto.key_id(), [0; MESSAGE_NONCE_LEN]);
+            header.segment_size = Some(segment_size);
+            header.encode().unwrap()
+        };
+        let with_frame = |mut stream: Vec<u8>, frame_len: u32| {
+            stream.extend_from_slice(&frame_len.to_le_bytes());
+            stream
+        };
+
+        // A segment size no writer accepts is refused with the header
+        for segment_size in [0, LAST_SEGMENT as usize / 2, u32::MAX as usize] {
+            let forged = with_frame(header(segment_size), LAST_SEGMENT - 1);
+            let err = stream_crypto().decrypt_stream(&forged[..], Vec::new(), &key(12345)).unwrap_err();
+            assert!(matches!(err, FskcError::FormatError(_)), "segment size {}", segment_size);
+        }
+
+        // A frame beyond the segment limit is refused before it is read
+        let forged = with_frame(header(SEGMENT), LAST_SEGMENT - 1);
+        let err = stream_crypto().decrypt_stream(&forged[..], Vec::new(), &key(12345)).unwrap_err();
+        assert!(matches!(err, FskcError::FormatError(_)));
+
+        // A large but valid claim only buffers the bytes actually present
+        let mut frame = Vec::new();
+        let mut reader = &[0u8; 10][..];
+        assert!(read_frame(&mut reader, &mut frame, 1 << 30).is_err());
+        assert!(frame.capacity() < 1 << 20);
+    }
+
+    #[test]
+    fn test_stream_detects_reordering() {
+        let data = vec![0x42; 3 * SEGMENT];
+        let (header, mut frames) = split_frames(&encrypt(&data));
+        frames.swap(0, 1);
+
+        let stream: Vec<u8> = header.into_iter().chain(frames.concat()).collect();
+        assert!(decrypt(&stream).is_err());
+    }
+
+    #[test]
+    fn test_stream_detects_truncation() {
+        let data = vec![0x42; 3 * SEGMENT];
+        let stream = encrypt(&data);
+        let (header, frames) = split_frames(&stream);
+
+        // Dropping the final segment leaves an unterminated stream
+        let truncated: Vec<u8> = header.iter().copied().chain(frames[..2].concat()).collect();
+        assert!(matches!(decrypt(&truncated), Err(FskcError::DecryptionError(_))));
+
+        // Cutting inside a segment is caught as well
+        assert!(decrypt(&stream[..stream.len() - 1]).is_err());
+
+        // Marking an earlier segment as final fails authentication
+        let mut forged = frames[0].clone();
+        forged[3] |= 0x80;
+        let forged: Vec<u8> = header.into_iter().chain(forged).collect();
+        assert!(decrypt(&forged).is_err());
+
+        // Trailing garbage after the final segment is rejected
+        let mut trailing = stream.clone();
+        trailing.push(0);
+        assert!(decrypt(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_stream_detects_tampering() {
+        let data = vec![0x42; 2 * SEGMENT];
+        let mut stream = encrypt(&data);
+        let last = stream.len() - 1;
+        stream[last] ^= 1;
+        assert!(decrypt(&stream).is_err());
+    }
+
+    #[test]
+    fn test_stream_and_message_formats_are_distinct() {
+        let data = b"not a stream".to_vec();
+        let mut crypto = stream_crypto();
+
+        let message = crypto.encrypt(&data, b"", &key(12345)).unwrap();
+        assert!(crypto.decrypt_stream(&message[..], Vec::new(), &key(12345)).is_err());
+
+        let stream = encrypt(&data);
+        assert!(crypto.open(&stream, b"", &key(12345)).is_err());
+    }
+}
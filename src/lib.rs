@@ -4,6 +4,8 @@
 //! geometric complexity rather than traditional computational complexity.
 #![ allow(warnings)]
 mod fractal;
+mod key;
+mod secret;
 mod roving;
 mod crypto;
 mod utils;
@@ -29,15 +31,24 @@ pub use triplet::{
     StateProof,
 };
 
-pub use fractal::FractalNode;
-pub use roving::RovingSelector;
+pub use fractal::{FractalNode, InclusionProof, MerkleHash};
+pub use key::Key256;
+pub use secret::SecretBytes;
+pub use roving::{Permutation, RovingSelector};
 pub use error::FskcError;
 pub use layered::{
     LayeredCrypto,
     LayerConfig,
     Layer,
+    LayerContext,
+    LayerParams,
+    LayerRegistry,
+    LayerFactory,
     SymmetricLayer,
     FractalLayer,
+    TranspositionLayer,
+    LayerKey,
+    EnvelopeHeader,
 };
 pub use entropy::{
     EntropyBuilder,
@@ -52,6 +63,7 @@ pub use entropy::sensor::{
     Accelerometer,
     Barometer,
 };
+pub use entropy::replay::{Recording, ReplaySensor};
 pub use holographic::HolographicKeyPackage;
 pub use holographic::compute::{HomomorphicCompute, Operation};
 pub use inside_out::{ComputePair, SystemState};
@@ -20,6 +20,8 @@ pub struct SharedContext {
 pub struct ContextConfig {
     /// Minimum required context quality
     pub min_quality: f64,
+    /// Minimum min-entropy per bit of a usable measurement window
+    pub min_entropy_per_bit: f64,
     /// Number of measurement windows required
     pub required_windows: usize,
     /// Maximum age of measurements
@@ -30,6 +32,7 @@ impl Default for ContextConfig {
     fn default() -> Self {
         Self {
             min_quality: 0.8,
+            min_entropy_per_bit: 0.5,
             required_windows: 3,
             max_age: Duration::from_secs(60),
         }
@@ -74,6 +77,13 @@ impl ContextManager {
                 continue;
             }
 
+            // Skip windows with too little min-entropy to rely on
+            if our_window.quality.min_entropy_per_bit < self.config.min_entropy_per_bit
+                || other_window.quality.min_entropy_per_bit < self.config.min_entropy_per_bit
+            {
+                continue;
+            }
+
             total_correlation += self.validator.calculate_correlation(our_window, other_window);
             total_sync += self.validator.calculate_sync_score(our_window, other_window);
             total_proximity += self.validator.calculate_proximity(our_window, other_window);
@@ -167,6 +177,7 @@ mod tests {
                     sample_rate: 100.0,
                     signal_to_noise: 10.0,
                     temporal_consistency: 1.0,
+                    min_entropy_per_bit: 1.0,
                 },
             };
             
@@ -179,6 +190,7 @@ mod tests {
                     sample_rate: 100.0,
                     signal_to_noise: 9.8,
                     temporal_consistency: 1.0,
+                    min_entropy_per_bit: 1.0,
                 },
             };
             
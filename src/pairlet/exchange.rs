@@ -1,6 +1,6 @@
 use std::time::{Duration, SystemTime};
 use sha2::{Sha256, Digest};
-use crate::Result;
+use crate::{secret, Result};
 use super::{DerivedKey, KeyGenerator, SharedContext};
 
 /// Status of key exchange process
@@ -112,8 +112,8 @@ impl KeyExchange {
             crate::FskcError::Custom("No confirmation hash for this round".into())
         })?;
 
-        // Compare hashes
-        let matches = our_hash == other_hash;
+        // Compare hashes in constant time
+        let matches = secret::ct_eq(our_hash, other_hash);
 
         // Update status if all rounds complete
         if round == self.config.confirmation_rounds - 1 && matches {
@@ -164,6 +164,7 @@ mod tests {
                         sample_rate: 100.0,
                         signal_to_noise: 10.0,
                         temporal_consistency: 1.0,
+                        min_entropy_per_bit: 0.9,
                     },
                 }
             ],
@@ -227,6 +228,7 @@ mod tests {
                         sample_rate: 100.0,
                         signal_to_noise: 2.0,
                         temporal_consistency: 0.5,
+                        min_entropy_per_bit: 0.3,
                     },
                 }
             ],
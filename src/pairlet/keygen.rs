@@ -1,13 +1,14 @@
 use std::time::SystemTime;
 use sha2::{Sha256, Digest};
-use crate::Result;
+use crate::{secret, Result, SecretBytes};
+use zeroize::Zeroize;
 use super::{SharedContext, MeasurementWindow};
 
 /// Key material derived from shared context
 #[derive(Debug, Clone)]
 pub struct DerivedKey {
-    /// Raw key bytes
-    pub key: Vec<u8>,
+    /// Raw key bytes, wiped when the key is dropped
+    pub key: SecretBytes,
     /// Key generation timestamp
     pub generated_at: SystemTime,
     /// Key quality score (0.0 to 1.0)
@@ -23,6 +24,8 @@ pub struct KeyGenConfig {
     pub key_length: usize,
     /// Minimum required key quality
     pub min_quality: f64,
+    /// Minimum min-entropy per bit of every measurement window
+    pub min_entropy_per_bit: f64,
     /// Number of hash iterations for KDF
     pub hash_iterations: usize,
 }
@@ -32,6 +35,7 @@ impl Default for KeyGenConfig {
         Self {
             key_length: 32,  // 256-bit keys
             min_quality: 0.8,
+            min_entropy_per_bit: 0.5,
             hash_iterations: 10000,
         }
     }
@@ -57,6 +61,13 @@ impl KeyGenerator {
             ));
         }
 
+        // Reject windows whose estimated min-entropy is too low to credit
+        if context.measurements.iter().any(|w| w.quality.min_entropy_per_bit < self.config.min_entropy_per_bit) {
+            return Err(crate::FskcError::Custom(
+                "Insufficient min-entropy for key generation".into()
+            ));
+        }
+
         // Collect entropy from all measurement windows
         let mut entropy = Vec::new();
         for window in &context.measurements {
@@ -65,6 +76,7 @@ impl KeyGenerator {
 
         // Apply key derivation function
         let key = self.derive_key(&entropy)?;
+        entropy.zeroize();
 
         // Calculate verification hash
         let mut hasher = Sha256::new();
@@ -101,34 +113,30 @@ impl KeyGenerator {
     }
 
     /// Apply key derivation function to entropy
-    fn derive_key(&self, entropy: &[u8]) -> Result<Vec<u8>> {
-        let mut key = entropy.to_vec();
+    fn derive_key(&self, entropy: &[u8]) -> Result<SecretBytes> {
+        let mut key = SecretBytes::from(entropy);
 
         // Apply multiple rounds of hashing
         for _ in 0..self.config.hash_iterations {
             let mut hasher = Sha256::new();
             hasher.update(&key);
-            key = hasher.finalize().to_vec();
+            key = SecretBytes::from(hasher.finalize().to_vec());
         }
 
-        // Truncate or pad to desired length
-        if key.len() >= self.config.key_length {
-            key.truncate(self.config.key_length);
-        } else {
-            while key.len() < self.config.key_length {
-                key.push(0); // Pad with zeros if needed
-            }
-        }
+        // Truncate or pad with zeros to desired length
+        let mut sized = SecretBytes::zeroed(self.config.key_length);
+        let len = key.len().min(sized.len());
+        sized[..len].copy_from_slice(&key[..len]);
 
-        Ok(key)
+        Ok(sized)
     }
 
-    /// Verify key matches verification hash
+    /// Verify key matches verification hash, in constant time
     pub fn verify_key(&self, key: &DerivedKey, verification_hash: &[u8]) -> bool {
         let mut hasher = Sha256::new();
         hasher.update(&key.key);
         let hash = hasher.finalize();
-        hash.as_slice() == verification_hash
+        secret::ct_eq(&hash, verification_hash)
     }
 }
 
@@ -153,6 +161,7 @@ mod tests {
                         sample_rate: 100.0,
                         signal_to_noise: 10.0,
                         temporal_consistency: 1.0,
+                        min_entropy_per_bit: 0.9,
                     },
                 }
             ],
@@ -172,6 +181,11 @@ mod tests {
         // Verify key verification
         assert!(generator.verify_key(&key, &key.verification_hash));
 
+        // Windows with too little min-entropy are refused
+        let mut weak = context.clone();
+        weak.measurements[0].quality.min_entropy_per_bit = 0.1;
+        assert!(generator.generate_key(&weak).is_err());
+
         Ok(())
     }
 
@@ -180,6 +194,7 @@ mod tests {
         let config = KeyGenConfig {
             key_length: 16,
             min_quality: 0.7,
+            min_entropy_per_bit: 0.5,
             hash_iterations: 1000,
         };
         let generator = KeyGenerator::new(config);
@@ -197,6 +212,7 @@ mod tests {
                         sample_rate: 100.0,
                         signal_to_noise: 8.0,
                         temporal_consistency: 0.9,
+                        min_entropy_per_bit: 0.8,
                     },
                 }
             ],
@@ -212,6 +228,10 @@ mod tests {
         let mut bad_key = key.clone();
         bad_key.key[0] ^= 1;
         assert!(!generator.verify_key(&bad_key, &key.verification_hash));
+        assert!(!generator.verify_key(&key, &key.verification_hash[..31]));
+
+        // Key bytes never reach debug output
+        assert!(format!("{:?}", key).contains("SecretBytes(..)"));
 
         Ok(())
     }
@@ -5,7 +5,7 @@ pub mod rotation;
 pub mod recovery;
 
 use std::time::{Duration, SystemTime};
-use crate::{Result, entropy::sensor::{Sensor, SensorConfig, EntropyQuality}};
+use crate::{Result, entropy::{estimators, sensor::{Sensor, SensorConfig, EntropyQuality}}};
 pub use context::{SharedContext, ContextConfig, ContextManager};
 pub use keygen::{DerivedKey, KeyGenConfig, KeyGenerator};
 pub use exchange::{ExchangeStatus, ExchangeConfig, KeyExchange};
@@ -153,6 +153,7 @@ impl CoPresenceValidator {
         for _ in 0..3 {
             // Create measurement window
             let mut measurements = Vec::new();
+            let mut samples = Vec::new();
             let mut total_quality = EntropyQuality::default();
             let mut count = 0;
 
@@ -167,6 +168,7 @@ impl CoPresenceValidator {
                     .collect();
                 
                 measurements.extend(sensor_measurements);
+                samples.extend_from_slice(&buffer);
 
                 // Update quality metrics
                 if let Ok(quality) = sensor.quality() {
@@ -184,6 +186,7 @@ impl CoPresenceValidator {
                 total_quality.temporal_consistency /= count as f64;
                 total_quality.sample_rate = sensor_config.sample_rate;
             }
+            total_quality.min_entropy_per_bit = estimators::estimate(&samples).min_entropy_per_bit();
 
             // Create and store measurement window
             let window = MeasurementWindow {
@@ -229,6 +232,9 @@ impl CoPresenceValidator {
 mod tests {
     use super::*;
     use crate::entropy::sensor::{Accelerometer, Barometer};
+    use crate::entropy::{Recording, ReplaySensor};
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
 
     #[test]
     fn test_copresence_validation() -> Result<()> {
@@ -246,6 +252,7 @@ mod tests {
                 sample_rate: 100.0,
                 signal_to_noise: 10.0,
                 temporal_consistency: 1.0,
+                min_entropy_per_bit: 1.0,
             },
         };
 
@@ -258,6 +265,7 @@ mod tests {
                 sample_rate: 100.0,
                 signal_to_noise: 9.8,  // Close to window1 for high proximity
                 temporal_consistency: 1.0,
+                min_entropy_per_bit: 1.0,
             },
         };
 
@@ -278,4 +286,35 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_start_collection_with_replay() -> Result<()> {
+        // Ten seconds of a noisy field recording at 100 Hz
+        let mut bytes = vec![0u8; 1000];
+        ChaCha20Rng::seed_from_u64(12345).fill_bytes(&mut bytes);
+        let mut recording = Recording::new(1)?;
+        for (i, &byte) in bytes.iter().enumerate() {
+            recording.push(Duration::from_millis(10 * i as u64), vec![byte as f64])?;
+        }
+
+        let config = CoPresenceConfig { window_size: Duration::from_millis(10), ..CoPresenceConfig::default() };
+        let mut validator = CoPresenceValidator::new(config);
+        validator.add_sensor(ReplaySensor::new(recording.clone(), "Device A"));
+        validator.add_sensor(ReplaySensor::new(recording, "Device B"));
+        validator.start_collection()?;
+        validator.stop_collection()?;
+
+        let windows = validator.recent_windows(3);
+        assert_eq!(windows.len(), 3);
+        for window in &windows {
+            assert_eq!(window.measurements.len(), 64);
+            // Both devices replay the same recording in step
+            assert_eq!(window.measurements[..32], window.measurements[32..]);
+            assert!(window.quality.min_entropy_per_bit > 0.0);
+            assert!(window.quality.signal_to_noise > 0.0);
+        }
+        assert_ne!(windows[0].measurements, windows[1].measurements);
+
+        Ok(())
+    }
 }
@@ -230,6 +230,7 @@ mod tests {
                         sample_rate: 100.0,
                         signal_to_noise: 10.0,
                         temporal_consistency: 1.0,
+                        min_entropy_per_bit: quality,
                     },
                 }
             ],
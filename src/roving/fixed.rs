@@ -0,0 +1,226 @@
+//! Integer-only backend for [`RovingSelector`](super::RovingSelector)
+//!
+//! Coordinates are Q16.16 fixed-point numbers held in `i64`, and the sine
+//! used to map bytes is evaluated in Q32.32 with integer arithmetic, so a
+//! selection sequence depends only on the seed and the data.
+
+use rand::RngCore;
+use rand_chacha::ChaCha20Rng;
+use std::collections::HashMap;
+
+/// Fractional bits of coordinates
+const FRAC_BITS: u32 = 16;
+/// 1.0 in Q16.16
+pub(crate) const ONE: i64 = 1 << FRAC_BITS;
+/// Bound of initial velocities, 0.1 in Q16.16
+const INITIAL_VELOCITY: i64 = 6554;
+/// Bound of the random velocity change per step, 0.05 in Q16.16
+const VELOCITY_JITTER: i64 = 3277;
+
+/// π in Q32.32
+const PI: i64 = 13_493_037_705;
+const HALF_PI: i64 = PI / 2;
+const TWO_PI: i64 = PI * 2;
+
+/// Particle with fixed-point position and velocity
+#[derive(Debug, Clone)]
+pub(super) struct Particle {
+    pub(super) position: Vec<i64>,
+    pub(super) velocity: Vec<i64>,
+}
+
+/// Particles and mapped points of a fixed-point selector
+#[derive(Debug, Clone)]
+pub(crate) struct Swarm {
+    dimension: usize,
+    pub(super) particles: Vec<Particle>,
+    /// First data index and position of every distinct byte, by index
+    points: Vec<(usize, Vec<i64>)>,
+}
+
+impl Swarm {
+    pub(crate) fn new(dimension: usize, num_particles: usize, rng: &mut ChaCha20Rng) -> Self {
+        let particles = (0..num_particles)
+            .map(|_| {
+                let position = (0..dimension).map(|_| uniform(rng, ONE)).collect();
+                let velocity = (0..dimension).map(|_| uniform(rng, INITIAL_VELOCITY)).collect();
+                Particle { position, velocity }
+            })
+            .collect();
+
+        Self {
+            dimension,
+            particles,
+            points: Vec::new(),
+        }
+    }
+
+    pub(crate) fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub(crate) fn map_data(&mut self, data: &[u8]) {
+        let mut first_seen = HashMap::new();
+        for (i, &byte) in data.iter().enumerate() {
+            first_seen.entry(byte).or_insert(i);
+        }
+
+        self.points = first_seen
+            .into_iter()
+            .map(|(byte, i)| (i, map_byte(byte, self.dimension)))
+            .collect();
+        self.points.sort_by_key(|&(i, _)| i);
+    }
+
+    /// Moves every particle one step
+    pub(crate) fn advance(&mut self, rng: &mut ChaCha20Rng) {
+        for particle in &mut self.particles {
+            for (p, v) in particle.position.iter_mut().zip(&particle.velocity) {
+                *p += v;
+            }
+            for v in &mut particle.velocity {
+                *v += uniform(rng, VELOCITY_JITTER);
+            }
+
+            let norm = isqrt(particle.velocity.iter().map(|&v| (v as i128 * v as i128) as u128).sum()) as i64;
+            if norm > ONE {
+                for v in &mut particle.velocity {
+                    *v = *v * ONE / norm;
+                }
+            }
+        }
+    }
+
+    /// Returns the index of the data point nearest to each particle
+    pub(crate) fn select(&self) -> Vec<usize> {
+        // Points are ordered by index, so the first minimum wins ties
+        self.particles
+            .iter()
+            .filter_map(|particle| {
+                self.points
+                    .iter()
+                    .map(|(idx, point)| (squared_distance(point, &particle.position), *idx))
+                    .min_by_key(|&(distance, _)| distance)
+                    .map(|(_, idx)| idx)
+            })
+            .collect()
+    }
+}
+
+/// Position of a byte: coordinate `j` is sin(byte · (j + 1) / 256)
+pub(crate) fn map_byte(byte: u8, dimension: usize) -> Vec<i64> {
+    (0..dimension)
+        .map(|j| sin(((byte as i64) * (j as i64 + 1)) << 24))
+        .collect()
+}
+
+/// Draws a value uniformly from `[-bound, bound)`
+fn uniform(rng: &mut ChaCha20Rng, bound: i64) -> i64 {
+    ((rng.next_u32() as i64 * 2 * bound) >> 32) - bound
+}
+
+fn squared_distance(a: &[i64], b: &[i64]) -> i128 {
+    a.iter()
+        .zip(b)
+        .map(|(&x, &y)| {
+            let d = (x - y) as i128;
+            d * d
+        })
+        .sum()
+}
+
+/// Sine of a Q32.32 angle in radians, as Q16.16
+pub(crate) fn sin(angle: i64) -> i64 {
+    // Reduce to [-π/2, π/2], where the series converges quickly
+    let mut x = angle.rem_euclid(TWO_PI);
+    if x > PI {
+        x -= TWO_PI;
+    }
+    if x > HALF_PI {
+        x = PI - x;
+    } else if x < -HALF_PI {
+        x = -PI - x;
+    }
+
+    // Taylor series up to x^11
+    let x2 = mul_q32(x, x);
+    let mut term = x;
+    let mut sum = x;
+    for k in 1..=5i64 {
+        term = -mul_q32(term, x2) / ((2 * k) * (2 * k + 1));
+        sum += term;
+    }
+    sum >> (32 - FRAC_BITS)
+}
+
+fn mul_q32(a: i64, b: i64) -> i64 {
+    ((a as i128 * b as i128) >> 32) as i64
+}
+
+/// Integer square root, rounded down
+pub(crate) fn isqrt(n: u128) -> u128 {
+    let mut root = 0u128;
+    let mut bit = 1u128 << ((127 - n.leading_zeros().min(127)) & !1);
+    let mut rest = n;
+    while bit != 0 {
+        if rest >= root + bit {
+            rest -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Key256, RovingSelector};
+
+    #[test]
+    fn test_sin_matches_floating_point() {
+        for byte in 0..=255i64 {
+            for j in 0..16i64 {
+                let expected = ((byte * (j + 1)) as f64 / 256.0).sin();
+                let actual = sin((byte * (j + 1)) << 24) as f64 / ONE as f64;
+                assert!((actual - expected).abs() < 1e-4, "sin({}/256)", byte * (j + 1));
+            }
+        }
+
+        // Pinned Q16.16 outputs, cross-checked as floor(sin(x) * 65536)
+        assert_eq!(map_byte(0, 2), [0, 0]);
+        assert_eq!(map_byte(128, 4), VECTOR_SIN_128);
+        assert_eq!(map_byte(255, 4), VECTOR_SIN_255);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..10_000u128 {
+            let root = isqrt(n);
+            assert!(root * root <= n && (root + 1) * (root + 1) > n);
+        }
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn test_selection_vectors() {
+        let data: Vec<u8> = (0..64u8).map(|i| i.wrapping_mul(37)).collect();
+        let mut selector = RovingSelector::new_fixed_point(4, 6, &Key256::insecure_from_u64(12345)).unwrap();
+        assert!(selector.is_fixed_point());
+        selector.map_data(&data).unwrap();
+
+        let steps: Vec<Vec<usize>> = (0..4).map(|_| selector.step_indices().unwrap()).collect();
+        assert_eq!(steps, VECTOR_SELECTIONS);
+    }
+
+    const VECTOR_SIN_128: [i64; 4] = [31419, 55146, 65371, 59591];
+    const VECTOR_SIN_255: [i64; 4] = [55007, 59802, 10008, -48923];
+    const VECTOR_SELECTIONS: [[usize; 6]; 4] = [
+        [7, 0, 42, 0, 28, 62],
+        [7, 0, 49, 0, 21, 62],
+        [0, 0, 63, 0, 7, 62],
+        [0, 0, 63, 0, 0, 62],
+    ];
+}
@@ -0,0 +1,98 @@
+use nalgebra::DVector;
+
+/// Relative slack when pruning, so rounding in `norm` never discards a
+/// branch that holds an equally distant point
+const PRUNE_SLACK: f64 = 1e-9;
+
+/// k-d tree over the mapped data points
+///
+/// Nearest-neighbour queries return the same point as an exhaustive scan
+/// that breaks distance ties by the smaller data index.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KdTree {
+    nodes: Vec<KdNode>,
+    root: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct KdNode {
+    point: DVector<f64>,
+    index: usize,
+    axis: usize,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+impl KdTree {
+    /// Builds a balanced tree over `(data index, point)` pairs
+    pub(crate) fn build(mut points: Vec<(usize, DVector<f64>)>) -> Self {
+        let mut tree = Self {
+            nodes: Vec::with_capacity(points.len()),
+            root: None,
+        };
+        tree.root = tree.build_node(&mut points, 0);
+        tree
+    }
+
+    fn build_node(&mut self, points: &mut [(usize, DVector<f64>)], depth: usize) -> Option<usize> {
+        if points.is_empty() {
+            return None;
+        }
+
+        let axis = depth % points[0].1.len();
+        points.sort_by(|a, b| a.1[axis].partial_cmp(&b.1[axis]).unwrap().then(a.0.cmp(&b.0)));
+        let median = points.len() / 2;
+
+        let (left, rest) = points.split_at_mut(median);
+        let (node, right) = rest.split_first_mut().unwrap();
+        let left = self.build_node(left, depth + 1);
+        let right = self.build_node(right, depth + 1);
+
+        self.nodes.push(KdNode {
+            point: node.1.clone(),
+            index: node.0,
+            axis,
+            left,
+            right,
+        });
+        Some(self.nodes.len() - 1)
+    }
+
+    /// Returns the data index of the point nearest to `target`
+    pub(crate) fn nearest(&self, target: &DVector<f64>) -> Option<usize> {
+        let mut best = None;
+        self.search(self.root, target, &mut best);
+        best.map(|(_, index)| index)
+    }
+
+    fn search(&self, node: Option<usize>, target: &DVector<f64>, best: &mut Option<(f64, usize)>) {
+        let Some(node) = node.map(|id| &self.nodes[id]) else {
+            return;
+        };
+
+        let distance = (&node.point - target).norm();
+        let closer = match *best {
+            Some((best_distance, best_index)) => {
+                distance < best_distance || (distance == best_distance && node.index < best_index)
+            }
+            None => true,
+        };
+        if closer {
+            *best = Some((distance, node.index));
+        }
+
+        let offset = target[node.axis] - node.point[node.axis];
+        let (near, far) = if offset < 0.0 {
+            (node.left, node.right)
+        } else {
+            (node.right, node.left)
+        };
+
+        self.search(near, target, best);
+        if let Some((best_distance, _)) = *best {
+            if offset.abs() <= best_distance * (1.0 + PRUNE_SLACK) {
+                self.search(far, target, best);
+            }
+        }
+    }
+}
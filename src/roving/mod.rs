@@ -0,0 +1,423 @@
+use crate::{error::FskcError, Key256, Result};
+use nalgebra::DVector;
+use rand::Rng;
+use rand_chacha::ChaCha20Rng;
+use std::collections::HashMap;
+
+mod fixed;
+mod kdtree;
+mod permutation;
+mod snapshot;
+
+use kdtree::KdTree;
+
+pub use permutation::Permutation;
+
+/// Represents a particle moving through the high-dimensional keyspace
+#[derive(Debug, Clone)]
+struct Particle {
+    position: DVector<f64>,
+    velocity: DVector<f64>,
+}
+
+/// Manages the selection of data points in high-dimensional space
+#[derive(Debug)]
+pub struct RovingSelector {
+    dimension: usize,
+    particles: Vec<Particle>,
+    data: Vec<u8>,
+    /// Spatial index over the distinct mapped points
+    index: KdTree,
+    /// Integer backend replacing the fields above when enabled
+    fixed: Option<fixed::Swarm>,
+    rng: ChaCha20Rng,
+    /// Steps taken since construction
+    steps: u64,
+}
+
+impl RovingSelector {
+    /// Creates a new RovingSelector with the specified parameters
+    pub fn new(
+        dimension: usize,
+        num_particles: usize,
+        seed: &Key256,
+    ) -> Result<Self> {
+        Self::validate(dimension, num_particles)?;
+
+        let mut rng = seed.rng();
+        let mut particles = Vec::with_capacity(num_particles);
+
+        // Initialize particles with random positions and velocities
+        for _ in 0..num_particles {
+            let position = DVector::from_fn(dimension, |_, _| rng.gen_range(-1.0..1.0));
+            let velocity = DVector::from_fn(dimension, |_, _| rng.gen_range(-0.1..0.1));
+            particles.push(Particle { position, velocity });
+        }
+
+        Ok(Self {
+            dimension,
+            particles,
+            data: Vec::new(),
+            index: KdTree::default(),
+            fixed: None,
+            rng,
+            steps: 0,
+        })
+    }
+
+    /// Creates a RovingSelector that moves particles with fixed-point
+    /// integer arithmetic
+    ///
+    /// Its selections are bit-identical on every platform for the same
+    /// seed and data, but differ from those of [`RovingSelector::new`].
+    pub fn new_fixed_point(
+        dimension: usize,
+        num_particles: usize,
+        seed: &Key256,
+    ) -> Result<Self> {
+        Self::validate(dimension, num_particles)?;
+
+        let mut rng = seed.rng();
+        let swarm = fixed::Swarm::new(dimension, num_particles, &mut rng);
+
+        Ok(Self {
+            dimension,
+            particles: Vec::new(),
+            data: Vec::new(),
+            index: KdTree::default(),
+            fixed: Some(swarm),
+            rng,
+            steps: 0,
+        })
+    }
+
+    fn validate(dimension: usize, num_particles: usize) -> Result<()> {
+        if dimension == 0 {
+            return Err(FskcError::GeometricError("Dimension cannot be zero".into()));
+        }
+
+        if num_particles == 0 {
+            return Err(FskcError::InvalidParticles(0));
+        }
+
+        Ok(())
+    }
+
+    /// Maps data points into the high-dimensional space
+    ///
+    /// Equal bytes map to the same point, so the spatial index holds only
+    /// the first occurrence of each byte value.
+    pub fn map_data(&mut self, data: &[u8]) -> Result<()> {
+        if data.is_empty() {
+            return Err(FskcError::InvalidDataSize(0));
+        }
+
+        // Clear existing data points
+        self.data = data.to_vec();
+        if let Some(swarm) = &mut self.fixed {
+            swarm.map_data(data);
+            return Ok(());
+        }
+        let mut first_seen = HashMap::new();
+
+        // Map each distinct byte to a point in high-dimensional space
+        for (i, &byte) in data.iter().enumerate() {
+            first_seen.entry(byte).or_insert_with(|| (i, self.map_byte(byte)));
+        }
+
+        self.index = KdTree::build(first_seen.into_values().collect());
+        Ok(())
+    }
+
+    /// Maps a single byte to its point in the high-dimensional space
+    fn map_byte(&self, byte: u8) -> DVector<f64> {
+        DVector::from_fn(self.dimension, |j, _| {
+            let phase = (byte as f64 * (j + 1) as f64) / 256.0;
+            phase.sin()
+        })
+    }
+
+    /// Points for every mapped byte, including repeated values
+    #[cfg(test)]
+    fn data_points(&self) -> Vec<DVector<f64>> {
+        self.data.iter().map(|&byte| self.map_byte(byte)).collect()
+    }
+
+    /// Moves particles through the space and selects nearby data points
+    ///
+    /// Returns the selected indices that fit in a `u8` and drops the rest;
+    /// use [`RovingSelector::step_indices`] or [`RovingSelector::step_bytes`]
+    /// for data longer than 256 bytes.
+    pub fn step(&mut self) -> Result<Vec<u8>> {
+        Ok(self.step_indices()?
+            .into_iter()
+            .filter_map(|idx| u8::try_from(idx).ok())
+            .collect())
+    }
+
+    /// Moves particles through the space and returns the data byte each
+    /// particle selected
+    pub fn step_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.step_indices()?
+            .into_iter()
+            .map(|idx| self.data[idx])
+            .collect())
+    }
+
+    /// Moves particles through the space and returns the index of the data
+    /// point each particle selected
+    pub fn step_indices(&mut self) -> Result<Vec<usize>> {
+        self.advance();
+
+        if let Some(swarm) = &self.fixed {
+            return Ok(swarm.select());
+        }
+
+        // Find nearest data point, preferring the lowest index on ties
+        Ok(self.particles
+            .iter()
+            .filter_map(|particle| self.index.nearest(&particle.position))
+            .collect())
+    }
+
+    /// Moves every particle one step; selections never feed back into
+    /// the motion, so this alone reproduces the particle trajectories
+    fn advance(&mut self) {
+        self.steps += 1;
+        if let Some(swarm) = &mut self.fixed {
+            swarm.advance(&mut self.rng);
+            return;
+        }
+
+        // Update particle positions
+        for particle in &mut self.particles {
+            // Update position based on velocity
+            particle.position += &particle.velocity;
+
+            // Add some random movement
+            let random_movement = DVector::from_fn(self.dimension, |_, _| {
+                self.rng.gen_range(-0.05..0.05)
+            });
+            particle.velocity += random_movement;
+
+            // Normalize velocity to prevent excessive speeds
+            if particle.velocity.norm() > 1.0 {
+                particle.velocity.normalize_mut();
+            }
+        }
+    }
+
+    /// Returns the number of steps taken since construction
+    pub fn step_count(&self) -> u64 {
+        self.steps
+    }
+
+    /// Moves the particles to where they are after `step_index` steps from
+    /// construction, as if `step` had been called that many times
+    ///
+    /// Seeking forward only advances the particles; seeking backward
+    /// replays the motion from the seed. The mapped data is kept.
+    pub fn seek(&mut self, step_index: u64) -> Result<()> {
+        if step_index < self.steps {
+            let seed = Key256::from_bytes(self.rng.get_seed());
+            let data = std::mem::take(&mut self.data);
+            *self = if self.is_fixed_point() {
+                Self::new_fixed_point(self.dimension, self.particle_count(), &seed)?
+            } else {
+                Self::new(self.dimension, self.particle_count(), &seed)?
+            };
+            if !data.is_empty() {
+                self.map_data(&data)?;
+            }
+        }
+
+        while self.steps < step_index {
+            self.advance();
+        }
+        Ok(())
+    }
+
+    /// Serializes the full selector state, including its seed and the
+    /// mapped data, so it must be stored like key material
+    pub fn snapshot(&self) -> Vec<u8> {
+        snapshot::encode(self)
+    }
+
+    /// Recreates a selector from [`RovingSelector::snapshot`]
+    ///
+    /// The restored selector continues with exactly the selections the
+    /// original would have made.
+    pub fn restore(data: &[u8]) -> Result<Self> {
+        snapshot::decode(data)
+    }
+
+    /// Returns the current dimension of the space
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the number of particles
+    pub fn particle_count(&self) -> usize {
+        match &self.fixed {
+            Some(swarm) => swarm.particle_count(),
+            None => self.particles.len(),
+        }
+    }
+
+    /// Returns true if the selector uses the fixed-point backend
+    pub fn is_fixed_point(&self) -> bool {
+        self.fixed.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roving_selector_creation() {
+        let selector = RovingSelector::new(4, 10, &Key256::insecure_from_u64(12345));
+        assert!(selector.is_ok());
+        
+        let selector = selector.unwrap();
+        assert_eq!(selector.dimension(), 4);
+        assert_eq!(selector.particle_count(), 10);
+    }
+
+    #[test]
+    fn test_data_mapping() {
+        let mut selector = RovingSelector::new(4, 10, &Key256::insecure_from_u64(12345)).unwrap();
+        let data = b"Test data".to_vec();
+        
+        assert!(selector.map_data(&data).is_ok());
+        assert_eq!(selector.data_points().len(), data.len());
+    }
+
+    #[test]
+    fn test_index_matches_brute_force() {
+        let mut selector = RovingSelector::new(6, 20, &Key256::insecure_from_u64(12345)).unwrap();
+        let data: Vec<u8> = (0..2000u32).map(|i| (i * 7 % 251) as u8).collect();
+        selector.map_data(&data).unwrap();
+        let points = selector.data_points();
+
+        for _ in 0..20 {
+            selector.step().unwrap();
+            for particle in &selector.particles {
+                let brute_force = points
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, point)| ((point - &particle.position).norm(), idx))
+                    .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(&b.1)))
+                    .map(|(_, idx)| idx);
+                assert_eq!(selector.index.nearest(&particle.position), brute_force);
+            }
+        }
+    }
+
+    #[test]
+    fn test_selection_beyond_256_bytes() {
+        let seed = Key256::insecure_from_u64(12345);
+        // Only bytes past offset 256 are distinct from the zero prefix
+        let mut data = vec![0u8; 300];
+        for (i, byte) in data[256..].iter_mut().enumerate() {
+            *byte = i as u8 * 5 + 1;
+        }
+
+        let mut indices = RovingSelector::new(4, 50, &seed).unwrap();
+        let mut bytes = RovingSelector::new(4, 50, &seed).unwrap();
+        let mut legacy = RovingSelector::new(4, 50, &seed).unwrap();
+        for selector in [&mut indices, &mut bytes, &mut legacy] {
+            selector.map_data(&data).unwrap();
+        }
+
+        for _ in 0..5 {
+            let selected = indices.step_indices().unwrap();
+            assert_eq!(selected.len(), 50);
+            assert!(selected.iter().any(|&idx| idx >= 256));
+
+            let expected: Vec<u8> = selected.iter().map(|&idx| data[idx]).collect();
+            assert_eq!(bytes.step_bytes().unwrap(), expected);
+
+            let small: Vec<u8> = selected.iter().filter(|&&idx| idx < 256).map(|&idx| idx as u8).collect();
+            assert_eq!(legacy.step().unwrap(), small);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data: Vec<u8> = (0..=255u8).rev().collect();
+
+        for fixed_point in [false, true] {
+            let mut selector = if fixed_point {
+                RovingSelector::new_fixed_point(5, 8, &seed).unwrap()
+            } else {
+                RovingSelector::new(5, 8, &seed).unwrap()
+            };
+            selector.map_data(&data).unwrap();
+            for _ in 0..3 {
+                selector.step_indices().unwrap();
+            }
+
+            let snapshot = selector.snapshot();
+            let mut restored = RovingSelector::restore(&snapshot).unwrap();
+            assert_eq!(restored.step_count(), 3);
+            assert_eq!(restored.is_fixed_point(), fixed_point);
+            assert_eq!(restored.snapshot(), snapshot);
+            for _ in 0..5 {
+                assert_eq!(restored.step_indices().unwrap(), selector.step_indices().unwrap());
+            }
+
+            for len in 0..snapshot.len() {
+                assert!(RovingSelector::restore(&snapshot[..len]).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_seek() {
+        let seed = Key256::insecure_from_u64(12345);
+        let data: Vec<u8> = (0..=255u8).collect();
+
+        for fixed_point in [false, true] {
+            let new = |seed: &Key256| if fixed_point {
+                RovingSelector::new_fixed_point(4, 6, seed).unwrap()
+            } else {
+                RovingSelector::new(4, 6, seed).unwrap()
+            };
+
+            let mut sender = new(&seed);
+            sender.map_data(&data).unwrap();
+            let sent: Vec<_> = (0..10).map(|_| sender.step_indices().unwrap()).collect();
+
+            // A receiver that missed the first seven steps resynchronizes
+            let mut receiver = new(&seed);
+            receiver.map_data(&data).unwrap();
+            receiver.seek(7).unwrap();
+            assert_eq!(receiver.step_indices().unwrap(), sent[7]);
+
+            // Seeking backward replays from the seed
+            receiver.seek(2).unwrap();
+            assert_eq!(receiver.step_count(), 2);
+            assert_eq!(receiver.step_indices().unwrap(), sent[2]);
+            assert_eq!(receiver.snapshot(), {
+                let mut replay = new(&seed);
+                replay.map_data(&data).unwrap();
+                replay.seek(3).unwrap();
+                replay.snapshot()
+            });
+        }
+    }
+
+    #[test]
+    fn test_particle_movement() {
+        let mut selector = RovingSelector::new(4, 10, &Key256::insecure_from_u64(12345)).unwrap();
+        let data = b"Test data".to_vec();
+        
+        selector.map_data(&data).unwrap();
+        let selected = selector.step().unwrap();
+        
+        assert!(!selected.is_empty());
+        assert!(selected.iter().all(|&idx| (idx as usize) < data.len()));
+    }
+}
@@ -0,0 +1,122 @@
+//! Binary snapshots of [`RovingSelector`] state
+//!
+//! Layout: magic, version, flags, dimension (u32 LE), particle count
+//! (u32 LE), steps taken (u64 LE), RNG seed, RNG stream (u64 LE), RNG word
+//! position (u128 LE), data length (u32 LE) and data, then every particle
+//! as its position and velocity coordinates. Coordinates are `f64` bit
+//! patterns, or `i64` values for the fixed-point backend, all little endian.
+
+use super::{fixed, Particle, RovingSelector};
+use crate::utils::Reader;
+use crate::{error::FskcError, Key256, Result};
+use nalgebra::DVector;
+
+const SNAPSHOT_MAGIC: [u8; 4] = *b"FSKR";
+const SNAPSHOT_VERSION: u8 = 1;
+
+/// Flag: the selector uses the fixed-point backend
+const FLAG_FIXED_POINT: u8 = 0x01;
+
+pub(super) fn encode(selector: &RovingSelector) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&SNAPSHOT_MAGIC);
+    out.push(SNAPSHOT_VERSION);
+    out.push(if selector.is_fixed_point() { FLAG_FIXED_POINT } else { 0 });
+    out.extend_from_slice(&(selector.dimension as u32).to_le_bytes());
+    out.extend_from_slice(&(selector.particle_count() as u32).to_le_bytes());
+    out.extend_from_slice(&selector.steps.to_le_bytes());
+    out.extend_from_slice(&selector.rng.get_seed());
+    out.extend_from_slice(&selector.rng.get_stream().to_le_bytes());
+    out.extend_from_slice(&selector.rng.get_word_pos().to_le_bytes());
+    out.extend_from_slice(&(selector.data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&selector.data);
+
+    match &selector.fixed {
+        Some(swarm) => {
+            for particle in &swarm.particles {
+                for value in particle.position.iter().chain(&particle.velocity) {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+        None => {
+            for particle in &selector.particles {
+                for value in particle.position.iter().chain(particle.velocity.iter()) {
+                    out.extend_from_slice(&value.to_bits().to_le_bytes());
+                }
+            }
+        }
+    }
+    out
+}
+
+pub(super) fn decode(data: &[u8]) -> Result<RovingSelector> {
+    let mut reader = Reader::new(data, "selector snapshot");
+    if reader.take(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+        return Err(FskcError::FormatError("Not a selector snapshot".into()));
+    }
+    let version = reader.u8()?;
+    if version != SNAPSHOT_VERSION {
+        return Err(FskcError::UnsupportedVersion(version));
+    }
+    let flags = reader.u8()?;
+    if flags & !FLAG_FIXED_POINT != 0 {
+        return Err(FskcError::FormatError(format!("Unknown snapshot flags {:#04x}", flags)));
+    }
+
+    let dimension = reader.u32()? as usize;
+    let count = reader.u32()? as usize;
+    let steps = reader.u64()?;
+    let seed = Key256::from_bytes(reader.array()?);
+    let stream = reader.u64()?;
+    let word_pos = u128::from_le_bytes(reader.array()?);
+    let data_len = reader.u32()? as usize;
+    let mapped = reader.take(data_len)?.to_vec();
+
+    // Checked before construction allocates the particles
+    let expected = count
+        .checked_mul(dimension)
+        .and_then(|coords| coords.checked_mul(16))
+        .ok_or_else(|| FskcError::FormatError("Snapshot dimensions out of range".into()))?;
+    if reader.remaining() != expected {
+        return Err(FskcError::FormatError("Snapshot particle data has the wrong length".into()));
+    }
+
+    // Construction validates the parameters; the particles are replaced below
+    let mut selector = if flags & FLAG_FIXED_POINT != 0 {
+        RovingSelector::new_fixed_point(dimension, count, &seed)?
+    } else {
+        RovingSelector::new(dimension, count, &seed)?
+    };
+
+    match &mut selector.fixed {
+        Some(swarm) => {
+            for particle in &mut swarm.particles {
+                *particle = fixed::Particle {
+                    position: read_coords(&mut reader, dimension, |bits| bits as i64)?,
+                    velocity: read_coords(&mut reader, dimension, |bits| bits as i64)?,
+                };
+            }
+        }
+        None => {
+            for particle in &mut selector.particles {
+                *particle = Particle {
+                    position: DVector::from_vec(read_coords(&mut reader, dimension, f64::from_bits)?),
+                    velocity: DVector::from_vec(read_coords(&mut reader, dimension, f64::from_bits)?),
+                };
+            }
+        }
+    }
+
+    selector.rng.set_stream(stream);
+    selector.rng.set_word_pos(word_pos);
+    selector.steps = steps;
+    if !mapped.is_empty() {
+        selector.map_data(&mapped)?;
+    }
+    Ok(selector)
+}
+
+fn read_coords<T>(reader: &mut Reader, dimension: usize, convert: impl Fn(u64) -> T) -> Result<Vec<T>> {
+    (0..dimension).map(|_| reader.u64().map(&convert)).collect()
+}
@@ -0,0 +1,111 @@
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// Heap buffer holding secret bytes
+///
+/// The buffer is wiped when dropped, redacted in `Debug` output and
+/// compared in constant time. It derefs to a slice rather than a `Vec`, so
+/// it can never reallocate and leave stale copies behind.
+#[derive(Clone, Default)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// Takes ownership of `bytes` without copying them
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns a buffer of `len` zero bytes
+    pub fn zeroed(len: usize) -> Self {
+        Self(vec![0; len])
+    }
+
+    /// Returns the secret bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl DerefMut for SecretBytes {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretBytes(..)")
+    }
+}
+
+/// Compares two byte strings in time independent of their contents
+///
+/// Only the lengths, which are not secret, can end the comparison early.
+pub(crate) fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equality_and_redaction() {
+        let secret = SecretBytes::from(vec![0xab; 16]);
+        assert_eq!(secret, SecretBytes::from(&[0xab; 16][..]));
+        assert_ne!(secret, SecretBytes::zeroed(16));
+        assert_ne!(secret, SecretBytes::from(vec![0xab; 15]));
+        assert_eq!(format!("{:?}", secret), "SecretBytes(..)");
+        assert_eq!(&secret[..2], &[0xab, 0xab]);
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        assert!(ct_eq(b"abc", b"abc"));
+        assert!(!ct_eq(b"abc", b"abd"));
+        assert!(!ct_eq(b"abc", b"ab"));
+        assert!(ct_eq(b"", b""));
+    }
+}
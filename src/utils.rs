@@ -60,6 +60,64 @@ fn validate_seed(seed: u64) -> Result<()> {
     }
 }
 
+/// Bounds-checked cursor over encoded data
+///
+/// `what` names the format in errors about truncated input.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    what: &'static str,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8], what: &'static str) -> Self {
+        Self { data, what }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(crate::error::FskcError::FormatError(format!("Truncated {}", self.what)));
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn string(&mut self, len: usize) -> Result<String> {
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| crate::error::FskcError::FormatError(format!("Invalid UTF-8 in {}", self.what)))
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len()
+    }
+
+    pub(crate) fn rest(self) -> &'a [u8] {
+        self.data
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,4 +1,4 @@
-use fskc::{FractalNode, RovingSelector, Result};
+use fskc::{FractalNode, Key256, RovingSelector, Result};
 use proptest::prelude::*;
 use test_case::test_case;
 
@@ -12,19 +12,17 @@ fn encrypt_decrypt_cycle(
     chunk_size: usize,
 ) -> Result<()> {
     // Generate seed using roving selector
-    let mut selector = RovingSelector::new(dimension, particles, 12345)?;
+    let mut selector = RovingSelector::new(dimension, particles, &Key256::insecure_from_u64(12345))?;
     selector.map_data(&shared_data)?;
     
-    let mut combined_seed = 0u64;
+    let mut selected = Vec::new();
     for _ in 0..5 {
-        let selected = selector.step()?;
-        for &byte in &selected {
-            combined_seed = combined_seed.wrapping_mul(256).wrapping_add(byte as u64);
-        }
+        selected.extend(selector.step()?);
     }
+    let seed = Key256::from_material(&selected);
 
     // Encrypt data
-    let encrypted = FractalNode::generate(data.clone(), combined_seed, depth, chunk_size)?;
+    let encrypted = FractalNode::generate(data.clone(), &seed, depth, chunk_size)?;
     
     // Decrypt data
     let decrypted = encrypted.decrypt()?;
@@ -162,21 +160,21 @@ mod error_tests {
     #[test]
     fn test_zero_depth() {
         let data = vec![1, 2, 3, 4];
-        let result = FractalNode::generate(data, 12345, 0, 2);
+        let result = FractalNode::generate(data, &Key256::insecure_from_u64(12345), 0, 2);
         assert!(matches!(result, Err(_)));
     }
 
     #[test]
     fn test_empty_data() {
         let data = vec![];
-        let result = FractalNode::generate(data, 12345, 3, 2);
+        let result = FractalNode::generate(data, &Key256::insecure_from_u64(12345), 3, 2);
         assert!(matches!(result, Err(_)));
     }
 
     #[test]
     fn test_invalid_chunk_size() {
         let data = vec![1, 2, 3, 4];
-        let result = FractalNode::generate(data, 12345, 3, 0);
+        let result = FractalNode::generate(data, &Key256::insecure_from_u64(12345), 3, 0);
         assert!(matches!(result, Err(_)));
     }
 }